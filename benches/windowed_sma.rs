@@ -0,0 +1,53 @@
+use async_std::task;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use manning_lp_async_rust_project_1_m1::signals::{AsyncStockSignal, WindowedSMA};
+use std::hint::black_box;
+
+///
+/// Roughly a year of minute bars of regular trading sessions
+///
+const SERIES_LEN: usize = 98_280;
+
+///
+/// The previous implementation, adding up every window again.
+/// Async like `AsyncStockSignal::calculate`, so both are timed through `block_on`.
+///
+async fn resummed_sma(series: &[f64], window_size: usize) -> Vec<f64> {
+    series
+        .windows(window_size)
+        .map(|w| w.iter().sum::<f64>() / w.len() as f64)
+        .collect()
+}
+
+fn series() -> Vec<f64> {
+    (0..SERIES_LEN)
+        .map(|i| 100.0 + (i as f64 * 0.01).sin() * 10.0)
+        .collect()
+}
+
+fn bench_windowed_sma(c: &mut Criterion) {
+    let series = series();
+    let mut group = c.benchmark_group("windowed_sma");
+    // half an hour, a day and a week of minute bars
+    for window_size in [30, 390, 1950] {
+        group.bench_with_input(
+            BenchmarkId::new("resummed", window_size),
+            &window_size,
+            |b, &window_size| {
+                b.iter(|| task::block_on(resummed_sma(black_box(&series), window_size)))
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("running_sum", window_size),
+            &window_size,
+            |b, &window_size| {
+                let signal = WindowedSMA { window_size };
+                b.iter(|| task::block_on(signal.calculate(black_box(&series))))
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_windowed_sma);
+criterion_main!(benches);
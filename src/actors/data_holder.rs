@@ -1,33 +1,185 @@
+use async_std::channel::{self, Sender};
 use async_trait::async_trait;
-use std::collections::VecDeque;
+use chrono::Utc;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::time::Duration;
 use xactor::*;
 
 use crate::messages::*;
+use crate::snapshot::{self, Snapshot};
+
+///
+/// Number of events buffered for a live subscriber before it is dropped as too slow
+///
+const SUBSCRIBER_BUFFER: usize = 256;
+
+///
+/// Largest number of missed events replayed to a resuming subscriber
+///
+pub const MAX_REPLAY: usize = 1000;
 
 #[derive(Clone)]
 pub struct DataHolder {
     pub indicators_vec: VecDeque<Indicators>,
+    pub symbols: HashMap<String, SymbolStatus>,
+    pub bars: HashMap<String, Vec<Bar>>,
+    subscribers: Vec<Sender<LiveEvent>>,
+    snapshot_path: Option<PathBuf>,
+    snapshot_interval: Duration,
 }
 
 impl DataHolder {
     pub fn new() -> Self {
         Self {
             indicators_vec: VecDeque::new(),
+            symbols: HashMap::new(),
+            bars: HashMap::new(),
+            subscribers: Vec::new(),
+            snapshot_path: None,
+            snapshot_interval: Duration::from_secs(60),
+        }
+    }
+
+    ///
+    /// Periodically persist the held indicators to `path`
+    ///
+    pub fn with_snapshot(mut self, path: PathBuf, interval: Duration) -> Self {
+        self.snapshot_path = Some(path);
+        self.snapshot_interval = interval;
+        self
+    }
+
+    ///
+    /// Replace the held indicators with the ones from a previous run
+    ///
+    pub fn restore(mut self, snapshot: Snapshot) -> Self {
+        self.indicators_vec = snapshot.indicators.into_iter().collect();
+        self
+    }
+
+    ///
+    /// Event id of the indicators at `index` in `indicators_vec`.
+    /// Ids count up from the oldest held indicators, so they stay
+    /// stable across snapshots as long as nothing is evicted.
+    ///
+    fn event_id(&self, index: usize) -> u64 {
+        (self.indicators_vec.len() - index) as u64
+    }
+
+    ///
+    /// Send an event to all live subscribers.
+    /// Subscribers that went away or can't keep up are dropped,
+    /// they can resume from the last event they have seen.
+    ///
+    fn publish_live(&mut self, event: LiveEvent) {
+        self.subscribers
+            .retain(|s| s.try_send(event.clone()).is_ok());
+    }
+
+    fn is_known(&self, symbol: &str) -> bool {
+        self.symbols.contains_key(symbol) || self.indicators_vec.iter().any(|i| i.symbol == symbol)
+    }
+
+    async fn save_snapshot(&self) {
+        if let Some(path) = &self.snapshot_path {
+            let snapshot = Snapshot {
+                saved_at: Utc::now(),
+                indicators: self.indicators_vec.iter().cloned().collect(),
+            };
+
+            if let Err(e) = snapshot::save(path, &snapshot).await {
+                eprintln!("Couldn't save snapshot to {}: {}", path.display(), e);
+            }
         }
     }
 }
 
+///
+/// Status of a symbol that has not been fetched yet
+///
+fn pending_status(symbol: String) -> SymbolStatus {
+    SymbolStatus {
+        symbol,
+        status: SymbolState::Pending,
+        last_fetch: None,
+        last_success: None,
+        error_count: 0,
+        last_error: None,
+    }
+}
+
 #[async_trait]
 impl Actor for DataHolder {
     async fn started(&mut self, ctx: &mut Context<Self>) -> Result<()> {
+        if self.snapshot_path.is_some() {
+            ctx.send_interval(SaveSnapshot, self.snapshot_interval);
+        }
+        ctx.subscribe::<FetchStatus>().await?;
+        ctx.subscribe::<Quote>().await?;
         ctx.subscribe::<Indicators>().await
     }
+
+    async fn stopped(&mut self, _ctx: &mut Context<Self>) {
+        self.save_snapshot().await;
+    }
 }
 
 #[async_trait]
 impl Handler<Indicators> for DataHolder {
     async fn handle(&mut self, _ctx: &mut Context<Self>, msg: Indicators) {
-        self.indicators_vec.push_front(msg);
+        self.indicators_vec.push_front(msg.clone());
+
+        if !self.subscribers.is_empty() {
+            let event = IndicatorsEvent {
+                id: self.event_id(0),
+                indicators: msg,
+            };
+            self.publish_live(LiveEvent::Indicators(Box::new(event)));
+        }
+    }
+}
+
+#[async_trait]
+impl Handler<FetchStatus> for DataHolder {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, msg: FetchStatus) {
+        let status = self
+            .symbols
+            .entry(msg.symbol.clone())
+            .or_insert_with(|| pending_status(msg.symbol.clone()));
+
+        status.last_fetch = Some(msg.fetched_at);
+        match msg.error {
+            None => {
+                status.status = SymbolState::Ok;
+                status.last_success = Some(msg.fetched_at);
+            }
+            Some(error) => {
+                status.status = SymbolState::Failing;
+                status.error_count += 1;
+                status.last_error = Some(error.clone());
+
+                self.publish_live(LiveEvent::Alert(Alert {
+                    symbol: msg.symbol,
+                    at: msg.fetched_at,
+                    message: format!("fetching quotes failed: {}", error),
+                }));
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Handler<Quote> for DataHolder {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, msg: Quote) {
+        self.bars.insert(msg.symbol, msg.bars);
+    }
+}
+
+#[async_trait]
+impl Handler<SaveSnapshot> for DataHolder {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _msg: SaveSnapshot) {
+        self.save_snapshot().await;
     }
 }
 
@@ -36,7 +188,7 @@ impl Handler<GetIndicators> for DataHolder {
     async fn handle(&mut self, _ctx: &mut Context<Self>, msg: GetIndicators) -> Vec<Indicators> {
         let n = msg.0;
 
-        if n <= 0 {
+        if n == 0 {
             return vec![];
         }
 
@@ -47,3 +199,138 @@ impl Handler<GetIndicators> for DataHolder {
             .collect::<Vec<Indicators>>()
     }
 }
+
+#[async_trait]
+impl Handler<GetSymbols> for DataHolder {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, msg: GetSymbols) -> Vec<SymbolStatus> {
+        msg.0
+            .into_iter()
+            .map(|symbol| match self.symbols.get(&symbol) {
+                Some(status) => status.clone(),
+                None => pending_status(symbol),
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Handler<GetLatestIndicators> for DataHolder {
+    async fn handle(
+        &mut self,
+        _ctx: &mut Context<Self>,
+        msg: GetLatestIndicators,
+    ) -> Option<Indicators> {
+        self.indicators_vec
+            .iter()
+            .find(|i| i.symbol == msg.0)
+            .cloned()
+    }
+}
+
+#[async_trait]
+impl Handler<GetHistory> for DataHolder {
+    ///
+    /// # Returns
+    ///
+    /// The page of matching indicators or the first requested symbol that is unknown.
+    /// Without any symbols, the indicators of all symbols are queried.
+    ///
+    async fn handle(
+        &mut self,
+        _ctx: &mut Context<Self>,
+        msg: GetHistory,
+    ) -> std::result::Result<HistoryPage, String> {
+        if let Some(unknown) = msg.symbols.iter().find(|s| !self.is_known(s)) {
+            return Err(unknown.clone());
+        }
+
+        let matching = self
+            .indicators_vec
+            .iter()
+            .enumerate()
+            .map(|(index, i)| (self.event_id(index), i))
+            .filter(|(_, i)| msg.symbols.is_empty() || msg.symbols.contains(&i.symbol))
+            .filter(|(_, i)| msg.from.is_none_or(|from| i.timestamp >= from))
+            .filter(|(_, i)| msg.to.is_none_or(|to| i.timestamp <= to));
+
+        // ids grow from the oldest indicators, so a cursor marks the same spot in both orders
+        let cursor = msg.cursor;
+        let ordered: Box<dyn Iterator<Item = (u64, &Indicators)>> = match msg.order {
+            Order::Desc => Box::new(matching.filter(move |(id, _)| cursor.is_none_or(|c| *id < c))),
+            Order::Asc => Box::new(
+                matching
+                    .rev()
+                    .filter(move |(id, _)| cursor.is_none_or(|c| *id > c)),
+            ),
+        };
+
+        // one more than asked for tells whether there is a next page
+        let mut page: Vec<(u64, &Indicators)> =
+            ordered.skip(msg.offset).take(msg.limit + 1).collect();
+        let has_more = page.len() > msg.limit;
+        page.truncate(msg.limit);
+
+        Ok(HistoryPage {
+            next_cursor: page.last().map(|(id, _)| *id).filter(|_| has_more),
+            indicators: page.into_iter().map(|(_, i)| i.clone()).collect(),
+        })
+    }
+}
+
+#[async_trait]
+impl Handler<GetBars> for DataHolder {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, msg: GetBars) -> Option<Vec<Bar>> {
+        self.bars.get(&msg.0).cloned()
+    }
+}
+
+#[async_trait]
+impl Handler<GetStoreStats> for DataHolder {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _msg: GetStoreStats) -> StoreStats {
+        StoreStats {
+            indicators: self.indicators_vec.len(),
+            bars: self.bars.values().map(Vec::len).sum(),
+            subscribers: self.subscribers.len(),
+        }
+    }
+}
+
+#[async_trait]
+impl Handler<SubscribeLive> for DataHolder {
+    async fn handle(
+        &mut self,
+        _ctx: &mut Context<Self>,
+        msg: SubscribeLive,
+    ) -> (Replay, channel::Receiver<LiveEvent>) {
+        let missed = match msg.last_event_id {
+            None => 0,
+            Some(last_event_id) => (0..self.indicators_vec.len())
+                .take_while(|index| self.event_id(*index) > last_event_id)
+                .count(),
+        };
+        let mut events: Vec<IndicatorsEvent> = self
+            .indicators_vec
+            .iter()
+            .take(missed.min(MAX_REPLAY))
+            .enumerate()
+            .map(|(index, indicators)| IndicatorsEvent {
+                id: self.event_id(index),
+                indicators: indicators.clone(),
+            })
+            .collect();
+        events.reverse();
+        let replay = Replay {
+            events,
+            dropped: missed.saturating_sub(MAX_REPLAY) as u64,
+        };
+
+        let (sender, receiver) = channel::bounded(SUBSCRIBER_BUFFER);
+        self.subscribers.push(sender);
+        (replay, receiver)
+    }
+}
+
+#[async_trait]
+impl Handler<Ping> for DataHolder {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _msg: Ping) {}
+}
@@ -1,7 +1,7 @@
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use futures::stream::{FuturesUnordered, StreamExt};
-use std::io::{Error, ErrorKind};
+use std::time::Instant;
 use xactor::*;
 use yahoo_finance_api as yahoo;
 
@@ -9,32 +9,45 @@ use crate::messages::*;
 
 pub struct Fetcher;
 
+fn error_kind(error: &yahoo::YahooError) -> FetchErrorKind {
+    match error {
+        yahoo::YahooError::ConnectionFailed => FetchErrorKind::Connection,
+        yahoo::YahooError::FetchFailed(_) => FetchErrorKind::Status,
+        yahoo::YahooError::DeserializeFailed(_) | yahoo::YahooError::InvalidJson => {
+            FetchErrorKind::Parse
+        }
+        yahoo::YahooError::EmptyDataSet => FetchErrorKind::EmptyData,
+        yahoo::YahooError::DataInconsistency => FetchErrorKind::Inconsistent,
+    }
+}
+
 impl Fetcher {
-    async fn fetch_data(symbol: String, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<f64> {
+    async fn fetch_data(
+        symbol: String,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> std::result::Result<Vec<Bar>, yahoo::YahooError> {
         let provider = yahoo::YahooConnector::new();
 
-        let res = provider
+        let response = provider
             .get_quote_history(symbol.as_str(), from, to)
-            .await
-            .map_err(|_| Error::from(ErrorKind::InvalidData));
+            .await?;
 
-        let mut series: Vec<f64> = Vec::new();
-        match res {
-            Err(_) => series,
-            Ok(response) => {
-                let mut quotes = response
-                    .quotes()
-                    .map_err(|_| Error::from(ErrorKind::InvalidData))
-                    .unwrap();
+        let mut quotes = response.quotes()?;
 
-                if !quotes.is_empty() {
-                    quotes.sort_by_cached_key(|k| k.timestamp);
-                    series = quotes.iter().map(|q| q.adjclose as f64).collect();
-                }
-
-                series
-            }
-        }
+        quotes.sort_by_cached_key(|k| k.timestamp);
+        Ok(quotes
+            .iter()
+            .map(|q| Bar {
+                timestamp: Utc.timestamp(q.timestamp as i64, 0),
+                open: q.open,
+                high: q.high,
+                low: q.low,
+                close: q.close,
+                adjclose: q.adjclose,
+                volume: q.volume,
+            })
+            .collect())
     }
 }
 
@@ -52,20 +65,42 @@ impl Handler<FetchQuotes> for Fetcher {
             .symbols
             .iter()
             .map(|symbol| {
-                let series = Fetcher::fetch_data(symbol.clone(), msg.from, msg.to);
-                let mut quote = Quote {
-                    symbol: symbol.clone(),
-                    from: msg.from,
-                    series: Vec::new(),
-                };
+                let bars = Fetcher::fetch_data(symbol.clone(), msg.from, msg.to);
+                let symbol = symbol.clone();
+                let from = msg.from;
+                let cycle = msg.cycle;
+
+                async move {
+                    let started = Instant::now();
+                    let result = bars.await;
+                    let status = FetchStatus {
+                        cycle,
+                        symbol: symbol.clone(),
+                        fetched_at: Utc::now(),
+                        duration: started.elapsed(),
+                        error: result.as_ref().err().map(|e| e.to_string()),
+                        error_kind: result.as_ref().err().map(error_kind),
+                    };
 
-                async {
-                    quote.series = series.await;
-                    let _ = Broker::from_registry().await.unwrap().publish(quote);
+                    if let Ok(bars) = result {
+                        let quote = Quote {
+                            cycle,
+                            symbol,
+                            from,
+                            bars,
+                        };
+                        let _ = Broker::from_registry().await.unwrap().publish(quote);
+                    }
+                    let _ = Broker::from_registry().await.unwrap().publish(status);
                 }
             })
             .collect();
 
-        while let Some(_) = cf.next().await {}
+        while cf.next().await.is_some() {}
     }
 }
+
+#[async_trait]
+impl Handler<Ping> for Fetcher {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _msg: Ping) {}
+}
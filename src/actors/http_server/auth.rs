@@ -0,0 +1,164 @@
+use tide::http::Method;
+use tide::{Next, Request, StatusCode};
+
+use super::error::*;
+use super::AppState;
+
+///
+/// Routes that answer without a token, so supervisors can probe the process
+/// and the dashboard can load before asking for a token
+///
+const PUBLIC_ROUTES: &[&str] = &["/", "/healthz", "/readyz"];
+
+///
+/// Streaming routes that also take the token as `?access_token=`, since
+/// browsers can't set headers on `EventSource` or WebSocket connections
+///
+const QUERY_TOKEN_ROUTES: &[&str] = &["/events", "/ws"];
+
+///
+/// What a token allows its bearer to do
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Scope {
+    ///
+    /// Read indicators, symbols, jobs and status
+    ///
+    Read,
+    ///
+    /// Read and also change the tracked symbols or trigger refreshes
+    ///
+    Admin,
+}
+
+impl Scope {
+    ///
+    /// Scope needed for a request: anything but reading needs an admin token
+    ///
+    pub fn required_for(method: Method) -> Self {
+        match method {
+            Method::Get | Method::Head => Scope::Read,
+            _ => Scope::Admin,
+        }
+    }
+}
+
+///
+/// Tokens accepted by the API. Without any token, authentication is disabled.
+///
+#[derive(Debug, Clone, Default)]
+pub struct Tokens {
+    read: Vec<String>,
+    admin: Vec<String>,
+}
+
+impl Tokens {
+    pub fn new(read: Vec<String>, admin: Vec<String>) -> Self {
+        let keep = |tokens: Vec<String>| -> Vec<String> {
+            tokens
+                .into_iter()
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect()
+        };
+        Self {
+            read: keep(read),
+            admin: keep(admin),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.read.is_empty() || !self.admin.is_empty()
+    }
+
+    ///
+    /// Scope granted by `token`, `None` if it isn't a known token
+    ///
+    pub fn scope_of(&self, token: &str) -> Option<Scope> {
+        // every token is compared, so the timing doesn't tell which one matched
+        let is_admin = self
+            .admin
+            .iter()
+            .fold(false, |found, t| found | eq(t, token));
+        let is_read = self
+            .read
+            .iter()
+            .fold(false, |found, t| found | eq(t, token));
+        if is_admin {
+            Some(Scope::Admin)
+        } else if is_read {
+            Some(Scope::Read)
+        } else {
+            None
+        }
+    }
+}
+
+///
+/// Compare two strings in time depending only on their lengths
+///
+fn eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+///
+/// Token sent with a request, either as `Authorization: Bearer <token>` or,
+/// on the streaming routes only, as `?access_token=`
+///
+fn request_token(req: &Request<AppState>) -> Option<String> {
+    if let Some(value) = req.header("Authorization") {
+        let value = value.as_str().trim();
+        let (scheme, token) = value.split_once(' ')?;
+        return scheme
+            .eq_ignore_ascii_case("bearer")
+            .then(|| token.trim().to_string());
+    }
+
+    if !QUERY_TOKEN_ROUTES.contains(&req.url().path()) {
+        return None;
+    }
+    req.url()
+        .query_pairs()
+        .find(|(key, _)| key == "access_token")
+        .map(|(_, token)| token.into_owned())
+}
+
+///
+/// Reject requests without a token that grants the needed scope
+///
+pub struct BearerAuth;
+
+#[tide::utils::async_trait]
+impl tide::Middleware<AppState> for BearerAuth {
+    async fn handle(&self, req: Request<AppState>, next: Next<'_, AppState>) -> tide::Result {
+        let tokens = &req.state().tokens;
+        if !tokens.is_enabled() || PUBLIC_ROUTES.contains(&req.url().path()) {
+            return Ok(next.run(req).await);
+        }
+
+        let required = Scope::required_for(req.method());
+        match request_token(&req).and_then(|token| tokens.scope_of(&token)) {
+            Some(scope) if scope >= required => Ok(next.run(req).await),
+            Some(_) => Ok(ApiError::new(
+                StatusCode::Forbidden,
+                "forbidden",
+                "this token is not allowed to change the tracker",
+            )
+            .into()),
+            None => {
+                let mut res: tide::Response = ApiError::new(
+                    StatusCode::Unauthorized,
+                    "unauthorized",
+                    "a valid bearer token is required",
+                )
+                .into();
+                res.insert_header("WWW-Authenticate", "Bearer");
+                Ok(res)
+            }
+        }
+    }
+}
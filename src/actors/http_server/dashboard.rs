@@ -0,0 +1,16 @@
+use tide::{Request, Response, StatusCode};
+
+use super::AppState;
+
+///
+/// Self-contained dashboard page, styles and scripts are inlined so it works offline
+///
+const DASHBOARD: &str = include_str!("dashboard.html");
+
+pub async fn get_dashboard(_req: Request<AppState>) -> tide::Result {
+    let mut res = Response::new(StatusCode::Ok);
+    res.set_body(DASHBOARD);
+    res.set_content_type(tide::http::mime::HTML);
+    res.insert_header("Cache-Control", "no-cache");
+    Ok(res)
+}
@@ -0,0 +1,86 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use tide::{Response, StatusCode};
+
+///
+/// Error returned by the API as `{"error": {"code": ..., "message": ...}}`
+///
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ApiError {
+    #[serde(skip)]
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn bad_request(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BadRequest, code, message)
+    }
+}
+
+impl From<ApiError> for Response {
+    fn from(err: ApiError) -> Self {
+        #[derive(Serialize)]
+        struct Body<'a> {
+            error: &'a ApiError,
+        }
+
+        json_response(err.status, &Body { error: &err })
+    }
+}
+
+///
+/// Build a response with a JSON body and the matching content type
+///
+pub fn json_response<T: Serialize>(status: StatusCode, body: &T) -> Response {
+    match serde_json::to_string(body) {
+        Ok(json) => Response::builder(status)
+            .body(json)
+            .content_type(tide::http::mime::JSON)
+            .build(),
+        Err(e) => ApiError::new(
+            StatusCode::InternalServerError,
+            "serialization_failed",
+            e.to_string(),
+        )
+        .into(),
+    }
+}
+
+///
+/// Turn errors and empty error responses produced by tide itself
+/// (unknown routes, failed actor calls, ...) into JSON error bodies.
+///
+pub async fn json_errors(res: Response) -> tide::Result {
+    if let Some(err) = res.error() {
+        let code = match err.status() {
+            StatusCode::BadRequest | StatusCode::UnprocessableEntity => "invalid_body",
+            status if status.is_server_error() => "internal_error",
+            _ => "error",
+        };
+        return Ok(ApiError::new(err.status(), code, err.to_string()).into());
+    }
+
+    let is_error = res.status().is_client_error() || res.status().is_server_error();
+    if is_error && res.is_empty().unwrap_or(false) {
+        let err = match res.status() {
+            StatusCode::NotFound => ApiError::new(res.status(), "not_found", "no such route"),
+            StatusCode::MethodNotAllowed => {
+                ApiError::new(res.status(), "method_not_allowed", "method not allowed")
+            }
+            status => ApiError::new(status, "error", status.canonical_reason()),
+        };
+        return Ok(err.into());
+    }
+
+    Ok(res)
+}
@@ -0,0 +1,153 @@
+use async_std::future::timeout;
+use async_std::io::BufReader;
+use futures::future;
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::pin::Pin;
+use std::time::Duration;
+use tide::{Body, Request, Response, StatusCode};
+
+use super::error::*;
+use super::AppState;
+use crate::messages::*;
+
+///
+/// Time without events after which a heartbeat comment is sent,
+/// so proxies don't close idle connections
+///
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+///
+/// Milliseconds a client should wait before reconnecting
+///
+pub const RETRY_MS: u64 = 3000;
+
+///
+/// Query parameters of `/events`
+///
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+    pub symbols: Option<String>,
+    pub last_event_id: Option<u64>,
+}
+
+enum Frame {
+    Event(LiveEvent),
+    Heartbeat,
+}
+
+///
+/// Parse a comma separated list of symbols.
+///
+/// # Returns
+///
+/// The upper-cased symbols or `None` if the list is empty, i.e. nothing is filtered.
+///
+pub fn parse_symbols(raw: Option<&str>) -> Option<HashSet<String>> {
+    let symbols: HashSet<String> = raw?
+        .split(',')
+        .map(|s| s.trim().to_uppercase())
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    if symbols.is_empty() {
+        None
+    } else {
+        Some(symbols)
+    }
+}
+
+///
+/// Encode a live event as a server-sent event.
+/// Only indicators carry an id, alerts are not replayed on resume.
+/// A `reset` event ahead of the replay tells a resuming client that
+/// it missed more indicators than are replayed.
+///
+pub fn encode_event(event: &LiveEvent) -> serde_json::Result<String> {
+    Ok(match event {
+        LiveEvent::Indicators(event) => format!(
+            "id: {}\nevent: indicators\ndata: {}\n\n",
+            event.id,
+            serde_json::to_string(&event.indicators)?
+        ),
+        LiveEvent::Alert(alert) => {
+            format!("event: alert\ndata: {}\n\n", serde_json::to_string(alert)?)
+        }
+    })
+}
+
+pub async fn stream_events(req: Request<AppState>) -> tide::Result {
+    let query: EventsQuery = match req.query() {
+        Ok(query) => query,
+        Err(e) => return Ok(ApiError::bad_request("invalid_parameter", e.to_string()).into()),
+    };
+
+    let last_event_id = match req.header("Last-Event-ID") {
+        None => query.last_event_id,
+        Some(value) => match value.as_str().trim().parse() {
+            Ok(id) => Some(id),
+            Err(_) => {
+                return Ok(ApiError::bad_request(
+                    "invalid_parameter",
+                    format!("Last-Event-ID must be an event id, got '{}'", value),
+                )
+                .into())
+            }
+        },
+    };
+    let symbols = parse_symbols(query.symbols.as_deref());
+
+    let (replay, receiver) = req
+        .state()
+        .data_holder
+        .call(SubscribeLive { last_event_id })
+        .await?;
+
+    let live = stream::unfold(receiver, |receiver| async move {
+        match timeout(HEARTBEAT_INTERVAL, receiver.recv()).await {
+            Ok(Ok(event)) => Some((Frame::Event(event), receiver)),
+            Ok(Err(_)) => None,
+            Err(_) => Some((Frame::Heartbeat, receiver)),
+        }
+    });
+
+    let mut preamble = format!("retry: {}\n: connected\n\n", RETRY_MS);
+    if replay.dropped > 0 {
+        // the oldest missed indicators aren't replayed, so the client has to reload the history
+        let data = serde_json::json!({ "dropped": replay.dropped });
+        preamble.push_str(&format!("event: reset\ndata: {}\n\n", data));
+    }
+    let frames = stream::iter(
+        replay
+            .events
+            .into_iter()
+            .map(|e| Frame::Event(LiveEvent::Indicators(Box::new(e)))),
+    )
+    .chain(live)
+    .filter_map(move |frame| {
+        let encoded = match frame {
+            Frame::Event(event) => match &symbols {
+                Some(symbols) if !symbols.contains(event.symbol()) => None,
+                _ => encode_event(&event).ok(),
+            },
+            Frame::Heartbeat => Some(": heartbeat\n\n".to_string()),
+        };
+        future::ready(encoded)
+    });
+
+    let body: Pin<Box<dyn Stream<Item = std::io::Result<Vec<u8>>> + Send + Sync>> = Box::pin(
+        stream::once(future::ready(preamble))
+            .chain(frames)
+            .map(|frame| Ok(frame.into_bytes())),
+    );
+
+    let mut res = Response::new(StatusCode::Ok);
+    res.set_body(Body::from_reader(
+        BufReader::new(body.into_async_read()),
+        None,
+    ));
+    res.set_content_type(tide::http::mime::SSE);
+    res.insert_header("Cache-Control", "no-cache");
+    Ok(res)
+}
@@ -0,0 +1,251 @@
+use flate2::write::{DeflateEncoder, GzEncoder};
+use flate2::Compression;
+use std::io::Write;
+use tide::{Request, Response, StatusCode};
+
+use super::error::*;
+use super::AppState;
+use crate::messages::*;
+use crate::registry::Column;
+
+///
+/// Smallest body worth compressing
+///
+pub const MIN_COMPRESS_SIZE: usize = 1024;
+
+///
+/// Representation of a list of indicators
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    JsonLines,
+    Csv,
+}
+
+impl Format {
+    fn from_param(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "json" => Some(Format::Json),
+            "jsonl" | "ndjson" => Some(Format::JsonLines),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+
+    fn from_mime(mime: &str) -> Option<Self> {
+        match mime {
+            "application/json" | "application/*" | "*/*" => Some(Format::Json),
+            "application/x-ndjson" | "application/jsonl" | "application/x-jsonlines" => {
+                Some(Format::JsonLines)
+            }
+            "text/csv" | "text/*" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Format::Json => "application/json",
+            Format::JsonLines => "application/x-ndjson",
+            Format::Csv => "text/csv; charset=utf-8",
+        }
+    }
+}
+
+///
+/// Content coding of a compressed response
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Gzip,
+    Deflate,
+}
+
+///
+/// Values of a header like `Accept` ordered by preference, values with `q=0` are left out
+///
+pub fn preferences(header: &str) -> Vec<String> {
+    let mut values: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let value = parts.next()?.trim().to_ascii_lowercase();
+            let q = parts
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse().ok())
+                .unwrap_or(1.0);
+            (!value.is_empty() && q > 0.0).then_some((value, q))
+        })
+        .collect();
+    // stable, so equally preferred values keep the order the client sent
+    values.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    values.into_iter().map(|(value, _)| value).collect()
+}
+
+///
+/// Pick the format of a response from `?format=` or else the `Accept` header
+///
+pub fn negotiate(req: &Request<AppState>) -> std::result::Result<Format, ApiError> {
+    let param = req
+        .url()
+        .query_pairs()
+        .find(|(key, _)| key == "format")
+        .map(|(_, value)| value.into_owned());
+    if let Some(param) = param {
+        return Format::from_param(&param).ok_or_else(|| {
+            ApiError::bad_request(
+                "invalid_parameter",
+                format!("format must be one of json, jsonl or csv, got '{}'", param),
+            )
+        });
+    }
+
+    let accept = match req.header("Accept") {
+        Some(accept) => accept.as_str(),
+        None => return Ok(Format::Json),
+    };
+    preferences(accept)
+        .iter()
+        .find_map(|mime| Format::from_mime(mime))
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::NotAcceptable,
+                "not_acceptable",
+                "supported types are application/json, application/x-ndjson and text/csv",
+            )
+        })
+}
+
+///
+/// Pick the compression of a response from the `Accept-Encoding` header
+///
+pub fn encoding(req: &Request<AppState>) -> Option<Encoding> {
+    let accept = req.header("Accept-Encoding")?;
+    preferences(accept.as_str())
+        .iter()
+        .find_map(|coding| match coding.as_str() {
+            "gzip" | "x-gzip" | "*" => Some(Encoding::Gzip),
+            "deflate" => Some(Encoding::Deflate),
+            _ => None,
+        })
+}
+
+///
+/// CSV header with a field for each of the indicator `columns`
+///
+pub fn csv_header(columns: &[Column]) -> String {
+    let mut header =
+        String::from("symbol,timestamp,from,last_price,pct_change,period_min,period_max");
+    for column in columns {
+        header.push(',');
+        header.push_str(&column.name);
+    }
+    header
+}
+
+///
+/// CSV record of indicators, a value that is missing or wasn't computed is left empty
+///
+pub fn csv_record(i: &Indicators, columns: &[Column]) -> String {
+    let mut record = format!(
+        "{},{},{},{},{},{},{}",
+        i.symbol,
+        i.timestamp.to_rfc3339(),
+        i.from.to_rfc3339(),
+        i.last_price,
+        i.pct_change,
+        i.period_min,
+        i.period_max,
+    );
+    for column in columns {
+        record.push(',');
+        if let Some(value) = i.values.get(&column.name).copied().flatten() {
+            record.push_str(&value.to_string());
+        }
+    }
+    record
+}
+
+fn encode(
+    format: Format,
+    indicators: &[Indicators],
+    columns: &[Column],
+) -> serde_json::Result<String> {
+    Ok(match format {
+        Format::Json => serde_json::to_string(indicators)?,
+        Format::JsonLines => {
+            let mut out = String::new();
+            for i in indicators {
+                out.push_str(&serde_json::to_string(i)?);
+                out.push('\n');
+            }
+            out
+        }
+        Format::Csv => {
+            let mut out = csv_header(columns);
+            out.push('\n');
+            for i in indicators {
+                out.push_str(&csv_record(i, columns));
+                out.push('\n');
+            }
+            out
+        }
+    })
+}
+
+fn compress(encoding: Encoding, body: &[u8]) -> std::io::Result<Vec<u8>> {
+    match encoding {
+        Encoding::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(body)?;
+            encoder.finish()
+        }
+        Encoding::Deflate => {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(body)?;
+            encoder.finish()
+        }
+    }
+}
+
+///
+/// Respond with indicators in the negotiated format, compressed if the client accepts it
+/// and the body is large enough for it to pay off. CSV has a field for each configured column.
+///
+pub fn indicators_response(req: &Request<AppState>, indicators: &[Indicators]) -> Response {
+    let format = match negotiate(req) {
+        Ok(format) => format,
+        Err(e) => return e.into(),
+    };
+    let body = match encode(format, indicators, &req.state().config.columns) {
+        Ok(body) => body.into_bytes(),
+        Err(e) => {
+            return ApiError::new(
+                StatusCode::InternalServerError,
+                "serialization_failed",
+                e.to_string(),
+            )
+            .into()
+        }
+    };
+
+    let mut res = Response::new(StatusCode::Ok);
+    res.insert_header("Content-Type", format.content_type());
+    res.insert_header("Vary", "Accept, Accept-Encoding");
+    match encoding(req).filter(|_| body.len() >= MIN_COMPRESS_SIZE) {
+        Some(coding) => match compress(coding, &body) {
+            Ok(compressed) => {
+                let name = match coding {
+                    Encoding::Gzip => "gzip",
+                    Encoding::Deflate => "deflate",
+                };
+                res.insert_header("Content-Encoding", name);
+                res.set_body(compressed);
+            }
+            Err(_) => res.set_body(body),
+        },
+        None => res.set_body(body),
+    }
+    res
+}
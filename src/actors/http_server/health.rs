@@ -0,0 +1,123 @@
+use async_std::future::timeout;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tide::{Request, StatusCode};
+
+use super::error::*;
+use super::AppState;
+use crate::messages::*;
+use crate::registry::Column;
+
+///
+/// Longest an actor may take to answer a ping before it is reported as busy
+///
+pub const PING_TIMEOUT: Duration = Duration::from_secs(1);
+
+///
+/// Settings the tracker was started with, reported by `/status`
+///
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct ConfigSummary {
+    pub symbols: Vec<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub snapshot_file: Option<String>,
+    pub snapshot_interval_secs: Option<u64>,
+    pub listen: Vec<String>,
+    pub auth: bool,
+    pub cors_origins: Vec<String>,
+    pub indicators: Vec<String>,
+    pub columns: Vec<Column>,
+}
+
+///
+/// Whether an actor still answers messages
+///
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ActorState {
+    Running,
+    Busy,
+    Stopped,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ErrorCounts {
+    pub fetch_errors: u64,
+    pub failing_symbols: usize,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Status {
+    pub started_at: DateTime<Utc>,
+    pub uptime_secs: i64,
+    pub config: ConfigSummary,
+    pub tracked_symbols: Vec<String>,
+    pub scheduler: SchedulerStatus,
+    pub actors: BTreeMap<&'static str, ActorState>,
+    pub errors: ErrorCounts,
+}
+
+pub async fn healthz(_req: Request<AppState>) -> tide::Result {
+    Ok(json_response(
+        StatusCode::Ok,
+        &serde_json::json!({ "status": "ok" }),
+    ))
+}
+
+pub async fn readyz(req: Request<AppState>) -> tide::Result {
+    let status = req.state().scheduler.call(GetSchedulerStatus).await?;
+    match status.last_successful_cycle {
+        Some(cycle) => Ok(json_response(
+            StatusCode::Ok,
+            &serde_json::json!({ "status": "ready", "last_successful_cycle": cycle }),
+        )),
+        None => Ok(ApiError::new(
+            StatusCode::ServiceUnavailable,
+            "not_ready",
+            "no fetch cycle has succeeded yet",
+        )
+        .into()),
+    }
+}
+
+pub async fn status(req: Request<AppState>) -> tide::Result {
+    let state = req.state();
+
+    let mut actors = BTreeMap::new();
+    for probe in state.actors.iter() {
+        let actor_state = match timeout(PING_TIMEOUT, probe.caller.call(Ping)).await {
+            Ok(Ok(())) => ActorState::Running,
+            Ok(Err(_)) => ActorState::Stopped,
+            Err(_) => ActorState::Busy,
+        };
+        actors.insert(probe.name, actor_state);
+    }
+
+    let scheduler = state.scheduler.call(GetSchedulerStatus).await?;
+    let tracked_symbols = state.scheduler.call(GetTrackedSymbols).await?;
+    let symbols = state
+        .data_holder
+        .call(GetSymbols(tracked_symbols.clone()))
+        .await?;
+
+    let now = Utc::now();
+    let status = Status {
+        started_at: state.started_at,
+        uptime_secs: (now - state.started_at).num_seconds(),
+        config: (*state.config).clone(),
+        tracked_symbols,
+        errors: ErrorCounts {
+            fetch_errors: scheduler.fetch_errors,
+            failing_symbols: symbols
+                .iter()
+                .filter(|s| s.status == SymbolState::Failing)
+                .count(),
+        },
+        scheduler,
+        actors,
+    };
+    Ok(json_response(StatusCode::Ok, &status))
+}
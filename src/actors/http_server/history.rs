@@ -0,0 +1,134 @@
+use chrono::prelude::*;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tide::{Request, Response};
+
+use super::error::*;
+use super::format::indicators_response;
+use super::symbols::{parse_symbol, symbol_param, unknown_symbol};
+use super::tail::parse_count;
+use super::AppState;
+use crate::messages::*;
+
+///
+/// Number of indicators returned by the history endpoints if no `limit` is given
+///
+pub const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+///
+/// Query parameters of `/history` and `/symbols/:symbol/history`
+///
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct HistoryQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<String>,
+    pub offset: Option<usize>,
+    pub cursor: Option<u64>,
+    #[serde(default)]
+    pub order: Order,
+    pub symbols: Option<String>,
+}
+
+///
+/// Link to the page after the one answered, keeping every other query parameter
+///
+pub fn next_link(req: &Request<AppState>, cursor: u64) -> String {
+    let mut url = req.url().clone();
+    let kept: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| key != "cursor" && key != "offset")
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(kept)
+        .append_pair("cursor", &cursor.to_string());
+    format!(
+        "<{}?{}>; rel=\"next\"",
+        url.path(),
+        url.query().unwrap_or_default()
+    )
+}
+
+///
+/// Validate the query of a history request and turn it into a `GetHistory`
+///
+fn parse_query(
+    req: &Request<AppState>,
+    symbols: Option<Vec<String>>,
+) -> std::result::Result<GetHistory, ApiError> {
+    let query: HistoryQuery = req
+        .query()
+        .map_err(|e| ApiError::bad_request("invalid_parameter", e.to_string()))?;
+
+    let limit = match query.limit.as_deref() {
+        None => DEFAULT_HISTORY_LIMIT,
+        Some(raw) => parse_count("limit", raw)?,
+    };
+
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from > to {
+            return Err(ApiError::bad_request(
+                "invalid_parameter",
+                "'from' is after 'to'",
+            ));
+        }
+    }
+
+    let symbols = match symbols {
+        Some(symbols) => symbols,
+        None => query
+            .symbols
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(parse_symbol)
+            .collect::<std::result::Result<_, _>>()?,
+    };
+
+    Ok(GetHistory {
+        symbols,
+        from: query.from,
+        to: query.to,
+        order: query.order,
+        cursor: query.cursor,
+        offset: query.offset.unwrap_or(0),
+        limit,
+    })
+}
+
+async fn respond(req: Request<AppState>, history: GetHistory) -> tide::Result {
+    let page = match req.state().data_holder.call(history).await? {
+        Ok(page) => page,
+        Err(unknown) => return Ok(unknown_symbol(&unknown).into()),
+    };
+
+    let mut res: Response = indicators_response(&req, &page.indicators);
+    if let Some(cursor) = page.next_cursor.filter(|_| res.status().is_success()) {
+        res.insert_header("Link", next_link(&req, cursor));
+    }
+    Ok(res)
+}
+
+///
+/// Indicators of all or the `symbols=` given symbols
+///
+pub async fn get_history(req: Request<AppState>) -> tide::Result {
+    match parse_query(&req, None) {
+        Ok(history) => respond(req, history).await,
+        Err(e) => Ok(e.into()),
+    }
+}
+
+///
+/// Indicators of the symbol in the path
+///
+pub async fn get_symbol_history(req: Request<AppState>) -> tide::Result {
+    let symbol = symbol_param(&req)?;
+    match parse_query(&req, Some(vec![symbol])) {
+        Ok(history) => respond(req, history).await,
+        Err(e) => Ok(e.into()),
+    }
+}
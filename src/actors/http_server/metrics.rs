@@ -0,0 +1,128 @@
+use async_std::future::timeout;
+use std::time::Instant;
+use tide::{Next, Request, Response, StatusCode};
+
+use super::health::PING_TIMEOUT;
+use super::{AppState, ROUTES};
+use crate::actors::metrics::{write_header, write_sample};
+use crate::messages::*;
+
+///
+/// Whether a request path matches the path of a route, where `:param` matches any segment
+///
+fn matches(route: &str, segments: &[&str]) -> bool {
+    let pattern: Vec<&str> = route.trim_matches('/').split('/').collect();
+    pattern.len() == segments.len()
+        && pattern
+            .iter()
+            .zip(segments)
+            .all(|(p, s)| p.starts_with(':') || p == s)
+}
+
+///
+/// Route label of a request path, `other` for paths that match no route.
+/// Routes with a path parameter are reported by their pattern,
+/// so the metrics don't get a label per symbol or job.
+///
+pub fn route_label(path: &str) -> String {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    ROUTES
+        .iter()
+        .map(|r| r.path)
+        .filter(|route| matches(route, &segments))
+        // like the router, prefer literal segments over parameters
+        .min_by_key(|route| route.matches(':').count())
+        .unwrap_or("other")
+        .to_string()
+}
+
+///
+/// Report every request served to the metrics actor
+///
+pub struct RequestMetrics;
+
+#[tide::utils::async_trait]
+impl tide::Middleware<AppState> for RequestMetrics {
+    async fn handle(&self, req: Request<AppState>, next: Next<'_, AppState>) -> tide::Result {
+        let started = Instant::now();
+        let method = req.method().to_string();
+        let route = route_label(req.url().path());
+        let metrics = req.state().metrics.clone();
+
+        let res = next.run(req).await;
+        let _ = metrics.send(HttpRequestServed {
+            method,
+            route,
+            status: res.status() as u16,
+            duration: started.elapsed(),
+        });
+        Ok(res)
+    }
+}
+
+pub async fn get_metrics(req: Request<AppState>) -> tide::Result {
+    let state = req.state();
+    let mut out = state.metrics.call(RenderMetrics).await?;
+
+    let stats = state.data_holder.call(GetStoreStats).await?;
+    let gauges = [
+        (
+            "stock_tracker_data_holder_indicators",
+            "Indicators held by the data holder",
+            stats.indicators,
+        ),
+        (
+            "stock_tracker_data_holder_bars",
+            "Price bars held by the data holder",
+            stats.bars,
+        ),
+        (
+            "stock_tracker_live_subscribers",
+            "Connected SSE and WebSocket subscribers",
+            stats.subscribers,
+        ),
+    ];
+    for (name, help, value) in gauges {
+        write_header(&mut out, name, help, "gauge");
+        write_sample(&mut out, name, &[], value);
+    }
+
+    let tracked = state.scheduler.call(GetTrackedSymbols).await?;
+    let symbols = state.data_holder.call(GetSymbols(tracked)).await?;
+    let name = "stock_tracker_last_success_timestamp_seconds";
+    write_header(
+        &mut out,
+        name,
+        "Unix time of the last successful fetch of a symbol",
+        "gauge",
+    );
+    for status in &symbols {
+        if let Some(last_success) = status.last_success {
+            let labels = [("symbol", status.symbol.as_str())];
+            write_sample(&mut out, name, &labels, last_success.timestamp());
+        }
+    }
+
+    // xactor doesn't expose mailbox lengths, the time an actor takes
+    // to answer a ping grows with its backlog instead
+    let name = "stock_tracker_actor_ping_seconds";
+    write_header(
+        &mut out,
+        name,
+        "Time taken by an actor to answer a ping, NaN if it didn't in time",
+        "gauge",
+    );
+    for probe in state.actors.iter() {
+        let started = Instant::now();
+        let latency = match timeout(PING_TIMEOUT, probe.caller.call(Ping)).await {
+            Ok(Ok(())) => started.elapsed().as_secs_f64(),
+            _ => f64::NAN,
+        };
+        write_sample(&mut out, name, &[("actor", probe.name)], latency);
+    }
+
+    let mut res = Response::new(StatusCode::Ok);
+    res.set_body(out);
+    res.insert_header("Content-Type", "text/plain; version=0.0.4; charset=utf-8");
+    Ok(res)
+}
@@ -0,0 +1,311 @@
+pub mod auth;
+pub mod dashboard;
+pub mod error;
+pub mod events;
+pub mod format;
+pub mod health;
+pub mod history;
+pub mod metrics;
+pub mod openapi;
+pub mod refresh;
+pub mod symbols;
+pub mod tail;
+pub mod ws;
+
+use async_std::task;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use tide::http::headers::HeaderValue;
+use tide::http::Method;
+use tide::listener::Listener;
+use tide::security::{CorsMiddleware, Origin};
+use tide::Endpoint;
+use tide_websockets::WebSocket;
+use xactor::*;
+
+use super::data_holder::*;
+use super::metrics::Metrics;
+use super::scheduler::*;
+use crate::messages::*;
+use error::*;
+
+///
+/// An actor whose liveness is reported by `/status`
+///
+#[derive(Clone)]
+pub struct ActorProbe {
+    pub name: &'static str,
+    pub caller: Arc<Caller<Ping>>,
+}
+
+///
+/// Actors the HTTP handlers talk to
+///
+#[derive(Clone)]
+pub struct AppState {
+    pub data_holder: Addr<DataHolder>,
+    pub scheduler: Addr<Scheduler>,
+    pub metrics: Addr<Metrics>,
+    pub started_at: DateTime<Utc>,
+    pub config: Arc<health::ConfigSummary>,
+    pub actors: Vec<ActorProbe>,
+    pub tokens: Arc<auth::Tokens>,
+    pub cors_origins: Vec<String>,
+}
+
+impl AppState {
+    pub fn new(
+        data_holder: Addr<DataHolder>,
+        scheduler: Addr<Scheduler>,
+        metrics: Addr<Metrics>,
+    ) -> Self {
+        let actors = vec![
+            ActorProbe {
+                name: "data_holder",
+                caller: Arc::new(data_holder.caller()),
+            },
+            ActorProbe {
+                name: "scheduler",
+                caller: Arc::new(scheduler.caller()),
+            },
+            ActorProbe {
+                name: "metrics",
+                caller: Arc::new(metrics.caller()),
+            },
+        ];
+        Self {
+            data_holder,
+            scheduler,
+            metrics,
+            started_at: Utc::now(),
+            config: Arc::new(health::ConfigSummary::default()),
+            actors,
+            tokens: Arc::new(auth::Tokens::default()),
+            cors_origins: vec![],
+        }
+    }
+
+    ///
+    /// Require a bearer token for the API, unless `tokens` is empty
+    ///
+    pub fn with_tokens(mut self, tokens: auth::Tokens) -> Self {
+        self.tokens = Arc::new(tokens);
+        self
+    }
+
+    ///
+    /// Let browsers on `origins` call the API, `*` allows any origin
+    ///
+    pub fn with_cors(mut self, origins: Vec<String>) -> Self {
+        self.cors_origins = origins;
+        self
+    }
+
+    pub fn with_config(mut self, config: health::ConfigSummary) -> Self {
+        self.config = Arc::new(config);
+        self
+    }
+
+    ///
+    /// Report the liveness of another actor in `/status`
+    ///
+    pub fn with_actor<A: Handler<Ping>>(mut self, name: &'static str, addr: &Addr<A>) -> Self {
+        self.actors.push(ActorProbe {
+            name,
+            caller: Arc::new(addr.caller()),
+        });
+        self
+    }
+}
+
+pub struct HttpServer {
+    pub state: AppState,
+}
+
+impl HttpServer {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+}
+
+#[async_trait]
+impl Actor for HttpServer {}
+
+#[async_trait]
+impl Handler<StartHttpServer> for HttpServer {
+    ///
+    /// Bind every address, then serve the API on all of them in the background.
+    ///
+    /// # Returns
+    ///
+    /// The URLs the API is served at, or the error of the first address that couldn't be bound.
+    ///
+    async fn handle(
+        &mut self,
+        _ctx: &mut Context<Self>,
+        msg: StartHttpServer,
+    ) -> std::io::Result<Vec<String>> {
+        let app = build_app(self.state.clone());
+
+        let mut listeners = vec![];
+        for addr in msg.0 {
+            let listener = app.clone().bind(addr).await.map_err(|e| {
+                std::io::Error::new(e.kind(), format!("couldn't listen on {}: {}", addr, e))
+            })?;
+            listeners.push(listener);
+        }
+
+        let mut urls = vec![];
+        for mut listener in listeners {
+            urls.extend(listener.info().iter().map(|i| i.connection().to_string()));
+            task::spawn(async move {
+                if let Err(e) = listener.accept().await {
+                    eprintln!("HTTP listener stopped: {}", e);
+                }
+            });
+        }
+        Ok(urls)
+    }
+}
+
+///
+/// A route of the HTTP API
+///
+pub struct Route {
+    pub method: Method,
+    ///
+    /// Path in tide's syntax, e.g. `/symbols/:symbol`
+    ///
+    pub path: &'static str,
+    pub endpoint: fn() -> Box<dyn Endpoint<AppState>>,
+}
+
+///
+/// Every route of the HTTP API. `build_app` registers them,
+/// the OpenAPI document and the request metrics are derived from them.
+///
+pub static ROUTES: &[Route] = &[
+    Route {
+        method: Method::Get,
+        path: "/",
+        endpoint: || Box::new(dashboard::get_dashboard),
+    },
+    Route {
+        method: Method::Get,
+        path: "/healthz",
+        endpoint: || Box::new(health::healthz),
+    },
+    Route {
+        method: Method::Get,
+        path: "/readyz",
+        endpoint: || Box::new(health::readyz),
+    },
+    Route {
+        method: Method::Get,
+        path: "/status",
+        endpoint: || Box::new(health::status),
+    },
+    Route {
+        method: Method::Get,
+        path: "/metrics",
+        endpoint: || Box::new(metrics::get_metrics),
+    },
+    Route {
+        method: Method::Get,
+        path: "/openapi.json",
+        endpoint: || Box::new(openapi::get_document),
+    },
+    Route {
+        method: Method::Get,
+        path: "/tail/:n",
+        endpoint: || Box::new(tail::get_indicators),
+    },
+    Route {
+        method: Method::Get,
+        path: "/symbols",
+        endpoint: || Box::new(symbols::list_symbols),
+    },
+    Route {
+        method: Method::Post,
+        path: "/symbols",
+        endpoint: || Box::new(symbols::add_symbols),
+    },
+    Route {
+        method: Method::Get,
+        path: "/symbols/:symbol",
+        endpoint: || Box::new(symbols::get_symbol),
+    },
+    Route {
+        method: Method::Delete,
+        path: "/symbols/:symbol",
+        endpoint: || Box::new(symbols::remove_symbol),
+    },
+    Route {
+        method: Method::Get,
+        path: "/symbols/:symbol/history",
+        endpoint: || Box::new(history::get_symbol_history),
+    },
+    Route {
+        method: Method::Get,
+        path: "/history",
+        endpoint: || Box::new(history::get_history),
+    },
+    Route {
+        method: Method::Get,
+        path: "/symbols/:symbol/bars",
+        endpoint: || Box::new(symbols::get_bars),
+    },
+    Route {
+        method: Method::Post,
+        path: "/refresh",
+        endpoint: || Box::new(refresh::refresh),
+    },
+    Route {
+        method: Method::Get,
+        path: "/refresh/:id",
+        endpoint: || Box::new(refresh::get_job),
+    },
+    Route {
+        method: Method::Get,
+        path: "/events",
+        endpoint: || Box::new(events::stream_events),
+    },
+    Route {
+        method: Method::Get,
+        path: "/ws",
+        endpoint: || Box::new(WebSocket::new(ws::session)),
+    },
+];
+
+///
+/// Set up the routes and middlewares of the HTTP API
+///
+pub fn build_app(state: AppState) -> tide::Server<AppState> {
+    let cors_origins = state.cors_origins.clone();
+    let mut app = tide::with_state(state);
+    app.with(metrics::RequestMetrics);
+    app.with(tide::utils::After(json_errors));
+    if !cors_origins.is_empty() {
+        // a list never matches `*` on its own
+        let origin = if cors_origins.iter().any(|origin| origin == "*") {
+            Origin::Any
+        } else {
+            Origin::from(cors_origins)
+        };
+        let header = |value: &str| value.parse::<HeaderValue>().unwrap();
+        app.with(
+            CorsMiddleware::new()
+                .allow_methods(header("GET, POST, DELETE, OPTIONS"))
+                .allow_headers(header("Authorization, Content-Type, Last-Event-ID"))
+                .expose_headers(header("Location, Link"))
+                .allow_origin(origin)
+                .allow_credentials(false),
+        );
+    }
+    app.with(auth::BearerAuth);
+    for route in ROUTES {
+        app.at(route.path).method(route.method, (route.endpoint)());
+    }
+    app
+}
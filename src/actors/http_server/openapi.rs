@@ -0,0 +1,459 @@
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::JsonSchema;
+use serde_json::{json, Value};
+use tide::{Request, StatusCode};
+
+use super::error::*;
+use super::health::Status;
+use super::refresh::RefreshBody;
+use super::symbols::AddSymbolsBody;
+use super::ws::{ClientMessage, ServerMessage};
+use super::{AppState, ROUTES};
+use crate::messages::*;
+
+///
+/// Path of a route in OpenAPI's syntax, e.g. `/symbols/{symbol}` for `/symbols/:symbol`
+///
+pub fn openapi_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(param) => format!("{{{}}}", param),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn schema<T: JsonSchema>(gen: &mut SchemaGenerator) -> Value {
+    serde_json::to_value(gen.subschema_for::<T>()).unwrap_or_default()
+}
+
+fn content(mime: &str, schema: Value) -> Value {
+    json!({ mime: { "schema": schema } })
+}
+
+fn response(description: &str, schema: Value) -> Value {
+    json!({ "description": description, "content": content("application/json", schema) })
+}
+
+fn error(description: &str) -> Value {
+    response(
+        description,
+        json!({ "$ref": "#/components/schemas/ErrorResponse" }),
+    )
+}
+
+fn path_param(name: &str, description: &str, schema: Value) -> Value {
+    json!({
+        "name": name,
+        "in": "path",
+        "required": true,
+        "description": description,
+        "schema": schema,
+    })
+}
+
+fn query_param(name: &str, description: &str, schema: Value) -> Value {
+    json!({
+        "name": name,
+        "in": "query",
+        "required": false,
+        "description": description,
+        "schema": schema,
+    })
+}
+
+fn format_param() -> Value {
+    query_param(
+        "format",
+        "Overrides the Accept header, one of json, jsonl or csv",
+        json!({ "type": "string", "enum": ["json", "jsonl", "csv"] }),
+    )
+}
+
+///
+/// Indicators in each of the formats the client can negotiate
+///
+fn indicators_list_response(list: Value, item: Value) -> Value {
+    json!({
+        "description": "Indicators, gzip or deflate compressed if large and accepted",
+        "content": {
+            "application/json": { "schema": list },
+            "application/x-ndjson": { "schema": item },
+            "text/csv": { "schema": { "type": "string" } },
+        },
+    })
+}
+
+///
+/// Parameters shared by the history endpoints, after `first`
+///
+fn history_params(first: &[Value]) -> Vec<Value> {
+    let timestamp = json!({ "type": "string", "format": "date-time" });
+    let count = json!({ "type": "integer", "minimum": 0 });
+    let mut params = first.to_vec();
+    params.extend(vec![
+        query_param("from", "Oldest indicators to return", timestamp.clone()),
+        query_param("to", "Newest indicators to return", timestamp),
+        query_param(
+            "order",
+            "Newest (desc, default) or oldest (asc) indicators first",
+            json!({ "type": "string", "enum": ["asc", "desc"] }),
+        ),
+        query_param(
+            "limit",
+            "Page size, at most 1000, defaults to 100",
+            count.clone(),
+        ),
+        query_param(
+            "cursor",
+            "Continue after this event id, as given by the `next` link",
+            count.clone(),
+        ),
+        query_param("offset", "Indicators to skip", count),
+        format_param(),
+    ]);
+    params
+}
+
+fn history_response(list: Value, item: Value) -> Value {
+    let mut response = indicators_list_response(list, item);
+    response["headers"] = json!({
+        "Link": {
+            "description": "`rel=\"next\"` link to the next page, if there is one",
+            "schema": { "type": "string" },
+        },
+    });
+    response
+}
+
+fn symbol_param() -> Value {
+    path_param(
+        "symbol",
+        "Stock symbol, case insensitive",
+        json!({ "type": "string" }),
+    )
+}
+
+///
+/// OpenAPI 3 description of every route of the HTTP API.
+/// The schemas are derived from the same types the handlers serialize.
+///
+pub fn document() -> Value {
+    let mut gen = SchemaSettings::openapi3().into_generator();
+    let indicators = schema::<Indicators>(&mut gen);
+    let indicators_list = schema::<Vec<Indicators>>(&mut gen);
+    let symbols = schema::<Vec<SymbolStatus>>(&mut gen);
+    let bars = schema::<Vec<Bar>>(&mut gen);
+    let job = schema::<RefreshJob>(&mut gen);
+    let status = schema::<Status>(&mut gen);
+    let cycle = schema::<CycleSummary>(&mut gen);
+    let add_symbols = schema::<AddSymbolsBody>(&mut gen);
+    let refresh = schema::<RefreshBody>(&mut gen);
+    let api_error = schema::<ApiError>(&mut gen);
+    let alert = schema::<Alert>(&mut gen);
+    let client_message = schema::<ClientMessage>(&mut gen);
+    let server_message = schema::<ServerMessage>(&mut gen);
+    let count = json!({ "type": "integer", "minimum": 0 });
+
+    let mut schemas = serde_json::Map::new();
+    for (name, schema) in gen.take_definitions() {
+        schemas.insert(name, serde_json::to_value(schema).unwrap_or_default());
+    }
+    // flattening drops the map of indicator values, whose columns are only known at runtime
+    if let Some(Value::Object(indicators)) = schemas.get_mut("Indicators") {
+        indicators.insert(
+            "additionalProperties".to_string(),
+            json!({
+                "oneOf": [
+                    { "type": "number", "format": "double" },
+                    { "type": "string", "format": "date-time" }
+                ],
+                "nullable": true,
+                "description": "Value of a configured indicator column, null until enough bars were seen. \
+                    Times like drawdown_peak are RFC 3339 strings."
+            }),
+        );
+    }
+    schemas.insert(
+        "ErrorResponse".to_string(),
+        json!({
+            "type": "object",
+            "required": ["error"],
+            "properties": { "error": api_error },
+        }),
+    );
+
+    // descriptions of the operations by OpenAPI path and method
+    let operations = json!({
+        "/": {
+            "get": {
+                "summary": "Dashboard with the latest indicators, updated live",
+                "security": [],
+                "responses": {
+                    "200": {
+                        "description": "HTML page",
+                        "content": content("text/html", json!({ "type": "string" })),
+                    },
+                },
+            },
+        },
+        "/healthz": {
+            "get": {
+                "summary": "Whether the process is up",
+                "security": [],
+                "responses": {
+                    "200": response("Up", json!({ "type": "object" })),
+                },
+            },
+        },
+        "/readyz": {
+            "get": {
+                "summary": "Whether at least one fetch cycle succeeded",
+                "security": [],
+                "responses": {
+                    "200": response("Ready", json!({
+                        "type": "object",
+                        "properties": { "last_successful_cycle": cycle },
+                    })),
+                    "503": error("No fetch cycle succeeded yet"),
+                },
+            },
+        },
+        "/status": {
+            "get": {
+                "summary": "Uptime, configuration, fetch cycles and actor states",
+                "responses": { "200": response("Status", status) },
+            },
+        },
+        "/metrics": {
+            "get": {
+                "summary": "Metrics in the Prometheus text format",
+                "responses": {
+                    "200": {
+                        "description": "Metrics",
+                        "content": content("text/plain", json!({ "type": "string" })),
+                    },
+                },
+            },
+        },
+        "/openapi.json": {
+            "get": {
+                "summary": "This document",
+                "responses": {
+                    "200": response("OpenAPI document", json!({ "type": "object" })),
+                },
+            },
+        },
+        "/tail/{n}": {
+            "get": {
+                "summary": "Most recent indicators of all symbols, newest first",
+                "parameters": [
+                    path_param("n", "Number of indicators, at most 1000", count.clone()),
+                    format_param(),
+                ],
+                "responses": {
+                    "200": indicators_list_response(indicators_list.clone(), indicators.clone()),
+                    "400": error("Invalid count"),
+                    "406": error("None of the accepted types is supported"),
+                },
+            },
+        },
+        "/symbols": {
+            "get": {
+                "summary": "Tracked symbols and their fetch status",
+                "responses": { "200": response("Symbols", symbols.clone()) },
+            },
+            "post": {
+                "summary": "Start tracking symbols",
+                "requestBody": {
+                    "required": true,
+                    "content": content("application/json", add_symbols),
+                },
+                "responses": {
+                    "200": response("Status of the added symbols", symbols),
+                    "400": error("Invalid body or symbol"),
+                },
+            },
+        },
+        "/symbols/{symbol}": {
+            "get": {
+                "summary": "Latest indicators of a symbol",
+                "parameters": [symbol_param()],
+                "responses": {
+                    "200": response("Indicators", indicators.clone()),
+                    "404": error("Unknown symbol"),
+                },
+            },
+            "delete": {
+                "summary": "Stop tracking a symbol",
+                "parameters": [symbol_param()],
+                "responses": {
+                    "204": { "description": "No longer tracked" },
+                    "404": error("Symbol wasn't tracked"),
+                },
+            },
+        },
+        "/symbols/{symbol}/history": {
+            "get": {
+                "summary": "Page of the indicators of a symbol over time",
+                "parameters": history_params(&[symbol_param()]),
+                "responses": {
+                    "200": history_response(indicators_list.clone(), indicators.clone()),
+                    "400": error("Invalid parameter"),
+                    "404": error("Unknown symbol"),
+                    "406": error("None of the accepted types is supported"),
+                },
+            },
+        },
+        "/history": {
+            "get": {
+                "summary": "Page of the indicators of several or all symbols over time",
+                "parameters": history_params(&[query_param(
+                    "symbols",
+                    "Comma separated symbols, all if empty",
+                    json!({ "type": "string" }),
+                )]),
+                "responses": {
+                    "200": history_response(indicators_list, indicators.clone()),
+                    "400": error("Invalid parameter"),
+                    "404": error("Unknown symbol"),
+                    "406": error("None of the accepted types is supported"),
+                },
+            },
+        },
+        "/symbols/{symbol}/bars": {
+            "get": {
+                "summary": "Price bars of the last fetch of a symbol",
+                "parameters": [symbol_param()],
+                "responses": {
+                    "200": response("Bars", bars),
+                    "404": error("Unknown symbol"),
+                },
+            },
+        },
+        "/refresh": {
+            "post": {
+                "summary": "Fetch quotes right away",
+                "parameters": [
+                    query_param(
+                        "wait",
+                        "Wait up to 30s for the fetch cycle, defaults to true",
+                        json!({ "type": "boolean" }),
+                    ),
+                ],
+                "requestBody": {
+                    "required": false,
+                    "content": content("application/json", refresh),
+                },
+                "responses": {
+                    "200": response("Completed job", job.clone()),
+                    "202": {
+                        "description": "Job still running, poll the `Location` header",
+                        "headers": { "Location": { "schema": { "type": "string" } } },
+                        "content": content("application/json", job.clone()),
+                    },
+                    "400": error("Invalid body or parameter"),
+                    "404": error("Symbol isn't tracked"),
+                },
+            },
+        },
+        "/refresh/{id}": {
+            "get": {
+                "summary": "State of a refresh job",
+                "parameters": [path_param("id", "Job id", count.clone())],
+                "responses": {
+                    "200": response("Job", job),
+                    "404": error("Unknown job"),
+                },
+            },
+        },
+        "/events": {
+            "get": {
+                "summary": "Server-sent events with new indicators and alerts",
+                "description": "`indicators` events carry an id to resume from, `alert` events don't.",
+                "parameters": [
+                    query_param(
+                        "symbols",
+                        "Comma separated symbols to receive events for, all if empty",
+                        json!({ "type": "string" }),
+                    ),
+                    query_param(
+                        "last_event_id",
+                        "Replay indicators after this event id",
+                        count.clone(),
+                    ),
+                    {
+                        "name": "Last-Event-ID",
+                        "in": "header",
+                        "required": false,
+                        "description": "Takes precedence over `last_event_id`",
+                        "schema": count.clone(),
+                    },
+                ],
+                "responses": {
+                    "200": {
+                        "description": "Event stream, `data` is Indicators or Alert JSON",
+                        "content": content("text/event-stream", json!({
+                            "oneOf": [indicators.clone(), alert],
+                        })),
+                    },
+                    "400": error("Invalid parameter"),
+                },
+            },
+        },
+        "/ws": {
+            "get": {
+                "summary": "WebSocket to subscribe to indicators and alerts",
+                "description": "Text frames carry JSON messages, tagged by `type`.",
+                "responses": {
+                    "101": {
+                        "description": "Switching to the WebSocket protocol",
+                        "content": {
+                            "application/json": {
+                                "schema": server_message,
+                                "x-client-messages": client_message,
+                            },
+                        },
+                    },
+                },
+            },
+        },
+    });
+    let mut paths = serde_json::Map::new();
+    for route in ROUTES {
+        let method = route.method.to_string().to_lowercase();
+        let path = openapi_path(route.path);
+        let operation = operations[&path][&method].clone();
+        if let Value::Object(item) = paths.entry(path).or_insert_with(|| json!({})) {
+            item.insert(method, operation);
+        }
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Stock tracker",
+            "description": "Indicators of periodically fetched stock quotes",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "components": {
+            "schemas": schemas,
+            "securitySchemes": {
+                "bearer": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "Only required if the tracker was started with tokens. \
+                        Reading needs a read or admin token, everything else an admin token. \
+                        `/events` and `/ws` also take `?access_token=` instead of the header.",
+                },
+            },
+        },
+        "security": [{}, { "bearer": [] }],
+        "paths": paths,
+    })
+}
+
+pub async fn get_document(_req: Request<AppState>) -> tide::Result {
+    Ok(json_response(StatusCode::Ok, &document()))
+}
@@ -0,0 +1,98 @@
+use async_std::future::timeout;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::time::Duration;
+use tide::{Request, StatusCode};
+
+use super::error::*;
+use super::symbols::{parse_symbol, unknown_symbol};
+use super::AppState;
+use crate::messages::*;
+
+///
+/// Longest a refresh request waits for its cycle before answering with the job id instead
+///
+pub const REFRESH_WAIT: Duration = Duration::from_secs(30);
+
+///
+/// Body of `POST /refresh`, all tracked symbols are refreshed if it is empty
+///
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct RefreshBody {
+    #[serde(default)]
+    pub symbols: Vec<String>,
+}
+
+///
+/// Query parameters of `POST /refresh`
+///
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RefreshQuery {
+    pub wait: Option<bool>,
+}
+
+fn accepted(job: &RefreshJob) -> tide::Response {
+    let mut res = json_response(StatusCode::Accepted, job);
+    res.insert_header("Location", format!("/refresh/{}", job.id));
+    res
+}
+
+pub async fn refresh(mut req: Request<AppState>) -> tide::Result {
+    let query: RefreshQuery = match req.query() {
+        Ok(query) => query,
+        Err(e) => return Ok(ApiError::bad_request("invalid_parameter", e.to_string()).into()),
+    };
+
+    let body = req.body_string().await?;
+    let body: RefreshBody = if body.trim().is_empty() {
+        RefreshBody::default()
+    } else {
+        match serde_json::from_str(&body) {
+            Ok(body) => body,
+            Err(e) => return Ok(ApiError::bad_request("invalid_body", e.to_string()).into()),
+        }
+    };
+
+    let symbols = match body
+        .symbols
+        .iter()
+        .map(|s| parse_symbol(s))
+        .collect::<std::result::Result<Vec<String>, ApiError>>()
+    {
+        Ok(symbols) => symbols,
+        Err(e) => return Ok(e.into()),
+    };
+
+    let tracked = req.state().scheduler.call(GetTrackedSymbols).await?;
+    if let Some(untracked) = symbols.iter().find(|s| !tracked.contains(s)) {
+        return Ok(unknown_symbol(untracked).into());
+    }
+
+    let (job, receiver) = req.state().scheduler.call(Refresh(symbols)).await?;
+    if !query.wait.unwrap_or(true) {
+        return Ok(accepted(&job));
+    }
+
+    match timeout(REFRESH_WAIT, receiver.recv()).await {
+        Ok(Ok(job)) => Ok(json_response(StatusCode::Ok, &job)),
+        _ => Ok(accepted(&job)),
+    }
+}
+
+pub async fn get_job(req: Request<AppState>) -> tide::Result {
+    let id = req.param("id")?;
+    let job = match id.parse() {
+        Ok(id) => req.state().scheduler.call(GetRefreshJob(id)).await?,
+        Err(_) => None,
+    };
+
+    match job {
+        Some(job) => Ok(json_response(StatusCode::Ok, &job)),
+        None => Ok(ApiError::new(
+            StatusCode::NotFound,
+            "unknown_job",
+            format!("refresh job '{}' doesn't exist", id),
+        )
+        .into()),
+    }
+}
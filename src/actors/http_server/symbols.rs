@@ -0,0 +1,128 @@
+use percent_encoding::percent_decode_str;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tide::{Request, Response, StatusCode};
+
+use super::error::*;
+use super::AppState;
+use crate::messages::*;
+
+///
+/// Longest symbol accepted when adding symbols
+///
+pub const MAX_SYMBOL_LEN: usize = 16;
+
+///
+/// Body of `POST /symbols`
+///
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AddSymbolsBody {
+    pub symbols: Vec<String>,
+}
+
+///
+/// Percent-decoded symbol of the path, e.g. `^GSPC` for `/symbols/%5EGSPC`
+///
+pub fn symbol_param(req: &Request<AppState>) -> tide::Result<String> {
+    let raw = req.param("symbol")?;
+    Ok(percent_decode_str(raw).decode_utf8_lossy().to_uppercase())
+}
+
+pub fn unknown_symbol(symbol: &str) -> ApiError {
+    ApiError::new(
+        StatusCode::NotFound,
+        "unknown_symbol",
+        format!("symbol '{}' is not tracked", symbol),
+    )
+}
+
+///
+/// Check and normalize a symbol given by a client
+///
+pub fn parse_symbol(raw: &str) -> std::result::Result<String, ApiError> {
+    let symbol = raw.trim().to_uppercase();
+    let is_valid = !symbol.is_empty()
+        && symbol.len() <= MAX_SYMBOL_LEN
+        && symbol
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ".-^=".contains(c));
+
+    if is_valid {
+        Ok(symbol)
+    } else {
+        Err(ApiError::bad_request(
+            "invalid_symbol",
+            format!("'{}' is not a valid symbol", raw),
+        ))
+    }
+}
+
+pub async fn list_symbols(req: Request<AppState>) -> tide::Result {
+    let tracked = req.state().scheduler.call(GetTrackedSymbols).await?;
+    let data: Vec<SymbolStatus> = req.state().data_holder.call(GetSymbols(tracked)).await?;
+    Ok(json_response(StatusCode::Ok, &data))
+}
+
+pub async fn add_symbols(mut req: Request<AppState>) -> tide::Result {
+    let body: AddSymbolsBody = match req.body_json().await {
+        Ok(body) => body,
+        Err(e) => return Ok(ApiError::bad_request("invalid_body", e.to_string()).into()),
+    };
+
+    if body.symbols.is_empty() {
+        return Ok(ApiError::bad_request("invalid_body", "no symbols given").into());
+    }
+    let symbols = match body
+        .symbols
+        .iter()
+        .map(|s| parse_symbol(s))
+        .collect::<std::result::Result<Vec<String>, ApiError>>()
+    {
+        Ok(symbols) => symbols,
+        Err(e) => return Ok(e.into()),
+    };
+
+    let tracked = req.state().scheduler.call(AddSymbols(symbols)).await?;
+    let data: Vec<SymbolStatus> = req.state().data_holder.call(GetSymbols(tracked)).await?;
+    Ok(json_response(StatusCode::Ok, &data))
+}
+
+pub async fn remove_symbol(req: Request<AppState>) -> tide::Result {
+    let symbol = symbol_param(&req)?;
+    if req
+        .state()
+        .scheduler
+        .call(RemoveSymbol(symbol.clone()))
+        .await?
+    {
+        Ok(Response::new(StatusCode::NoContent))
+    } else {
+        Ok(unknown_symbol(&symbol).into())
+    }
+}
+
+pub async fn get_symbol(req: Request<AppState>) -> tide::Result {
+    let symbol = symbol_param(&req)?;
+    match req
+        .state()
+        .data_holder
+        .call(GetLatestIndicators(symbol.clone()))
+        .await?
+    {
+        Some(data) => Ok(json_response(StatusCode::Ok, &data)),
+        None => Ok(unknown_symbol(&symbol).into()),
+    }
+}
+
+pub async fn get_bars(req: Request<AppState>) -> tide::Result {
+    let symbol = symbol_param(&req)?;
+    match req
+        .state()
+        .data_holder
+        .call(GetBars(symbol.clone()))
+        .await?
+    {
+        Some(data) => Ok(json_response(StatusCode::Ok, &data)),
+        None => Ok(unknown_symbol(&symbol).into()),
+    }
+}
@@ -0,0 +1,54 @@
+use tide::Request;
+
+use super::error::*;
+use super::format::indicators_response;
+use super::AppState;
+use crate::messages::*;
+
+///
+/// Largest number of indicators a single request may ask for
+///
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+///
+/// Parse the number of indicators requested in the parameter `name`.
+///
+/// # Returns
+///
+/// The count or an `ApiError` if it is not a non-negative integer
+/// or larger than `MAX_PAGE_SIZE`.
+///
+pub fn parse_count(name: &str, raw: &str) -> std::result::Result<usize, ApiError> {
+    let digits = raw.strip_prefix('-').unwrap_or(raw);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ApiError::bad_request(
+            "invalid_parameter",
+            format!("{} must be an integer, got '{}'", name, raw),
+        ));
+    }
+
+    let is_zero = digits.bytes().all(|b| b == b'0');
+    if raw.starts_with('-') && !is_zero {
+        return Err(ApiError::bad_request(
+            "invalid_parameter",
+            format!("{} must not be negative, got {}", name, raw),
+        ));
+    }
+
+    match digits.parse::<usize>() {
+        Ok(n) if n <= MAX_PAGE_SIZE => Ok(n),
+        _ => Err(ApiError::bad_request(
+            "page_size_exceeded",
+            format!("{} must not exceed {}, got {}", name, MAX_PAGE_SIZE, raw),
+        )),
+    }
+}
+
+pub async fn get_indicators(req: Request<AppState>) -> tide::Result {
+    let n = match parse_count("n", req.param("n")?) {
+        Ok(n) => n,
+        Err(e) => return Ok(e.into()),
+    };
+    let data: Vec<Indicators> = req.state().data_holder.call(GetIndicators(n)).await?;
+    Ok(indicators_response(&req, &data))
+}
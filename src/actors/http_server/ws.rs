@@ -0,0 +1,241 @@
+use futures::future;
+use futures::stream::{self, StreamExt};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+use tide::Request;
+use tide_websockets::{Message, WebSocketConnection};
+use xactor::*;
+
+use super::AppState;
+use crate::messages::*;
+
+///
+/// Symbol to subscribe to every tracked symbol at once
+///
+pub const WILDCARD: &str = "*";
+
+///
+/// Messages a client sends over the WebSocket
+///
+#[derive(Debug, PartialEq, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Subscribe {
+        symbols: Vec<String>,
+    },
+    Unsubscribe {
+        symbols: Vec<String>,
+    },
+    Snapshot {
+        #[serde(default)]
+        symbols: Vec<String>,
+    },
+    Ping {
+        #[serde(default)]
+        id: Option<serde_json::Value>,
+    },
+}
+
+///
+/// Messages the server sends over the WebSocket
+///
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Subscribed {
+        symbols: Vec<String>,
+    },
+    Indicators {
+        id: u64,
+        indicators: Box<Indicators>,
+    },
+    Alert(Alert),
+    Snapshot {
+        indicators: Vec<Indicators>,
+    },
+    Pong {
+        id: Option<serde_json::Value>,
+    },
+    Error {
+        code: &'static str,
+        message: String,
+    },
+}
+
+impl From<LiveEvent> for ServerMessage {
+    fn from(event: LiveEvent) -> Self {
+        match event {
+            LiveEvent::Indicators(event) => ServerMessage::Indicators {
+                id: event.id,
+                indicators: Box::new(event.indicators),
+            },
+            LiveEvent::Alert(alert) => ServerMessage::Alert(alert),
+        }
+    }
+}
+
+///
+/// Symbols a single connection is subscribed to
+///
+#[derive(Debug, Default)]
+pub struct Subscriptions {
+    all: bool,
+    symbols: HashSet<String>,
+}
+
+impl Subscriptions {
+    pub fn subscribe(&mut self, symbols: &[String]) {
+        for symbol in symbols {
+            if symbol == WILDCARD {
+                self.all = true;
+            } else {
+                self.symbols.insert(symbol.trim().to_uppercase());
+            }
+        }
+    }
+
+    pub fn unsubscribe(&mut self, symbols: &[String]) {
+        for symbol in symbols {
+            if symbol == WILDCARD {
+                self.all = false;
+                self.symbols.clear();
+            } else {
+                self.symbols.remove(&symbol.trim().to_uppercase());
+            }
+        }
+    }
+
+    pub fn matches(&self, symbol: &str) -> bool {
+        self.all || self.symbols.contains(symbol)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.all && self.symbols.is_empty()
+    }
+
+    ///
+    /// The subscribed symbols in alphabetical order, `*` if subscribed to all
+    ///
+    pub fn list(&self) -> Vec<String> {
+        if self.all {
+            vec![WILDCARD.to_string()]
+        } else {
+            self.symbols
+                .iter()
+                .cloned()
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect()
+        }
+    }
+}
+
+enum Input {
+    Client(std::result::Result<Message, tide_websockets::Error>),
+    Live(LiveEvent),
+    LiveClosed,
+}
+
+///
+/// Latest indicators of the requested symbols.
+/// Without an explicit list, the subscribed symbols are used
+/// or every tracked symbol if the connection subscribed to all or nothing.
+///
+async fn snapshot(
+    state: &AppState,
+    subscriptions: &Subscriptions,
+    symbols: Vec<String>,
+) -> Result<Vec<Indicators>> {
+    let symbols = if !symbols.is_empty() {
+        symbols.iter().map(|s| s.trim().to_uppercase()).collect()
+    } else if subscriptions.is_empty() || subscriptions.all {
+        state.scheduler.call(GetTrackedSymbols).await?
+    } else {
+        subscriptions.list()
+    };
+
+    let mut indicators = vec![];
+    for symbol in symbols {
+        if let Some(latest) = state.data_holder.call(GetLatestIndicators(symbol)).await? {
+            indicators.push(latest);
+        }
+    }
+    Ok(indicators)
+}
+
+async fn handle_client(
+    state: &AppState,
+    subscriptions: &mut Subscriptions,
+    msg: ClientMessage,
+) -> Result<ServerMessage> {
+    Ok(match msg {
+        ClientMessage::Subscribe { symbols } => {
+            subscriptions.subscribe(&symbols);
+            ServerMessage::Subscribed {
+                symbols: subscriptions.list(),
+            }
+        }
+        ClientMessage::Unsubscribe { symbols } => {
+            subscriptions.unsubscribe(&symbols);
+            ServerMessage::Subscribed {
+                symbols: subscriptions.list(),
+            }
+        }
+        ClientMessage::Snapshot { symbols } => ServerMessage::Snapshot {
+            indicators: snapshot(state, subscriptions, symbols).await?,
+        },
+        ClientMessage::Ping { id } => ServerMessage::Pong { id },
+    })
+}
+
+///
+/// Serve one WebSocket connection until the client goes away
+///
+pub async fn session(req: Request<AppState>, conn: WebSocketConnection) -> tide::Result<()> {
+    let state = req.state().clone();
+    let (_, live) = state
+        .data_holder
+        .call(SubscribeLive {
+            last_event_id: None,
+        })
+        .await?;
+
+    let mut subscriptions = Subscriptions::default();
+    let live = live
+        .map(Input::Live)
+        .chain(stream::once(future::ready(Input::LiveClosed)));
+    let mut inputs = stream::select(conn.clone().map(Input::Client), live);
+
+    while let Some(input) = inputs.next().await {
+        match input {
+            Input::Live(event) => {
+                if subscriptions.matches(event.symbol()) {
+                    conn.send_json(&ServerMessage::from(event)).await?;
+                }
+            }
+            Input::LiveClosed => {
+                let lagged = ServerMessage::Error {
+                    code: "lagged",
+                    message: "connection couldn't keep up with live updates".to_string(),
+                };
+                conn.send_json(&lagged).await?;
+                break;
+            }
+            Input::Client(Ok(Message::Text(text))) => {
+                let reply = match serde_json::from_str::<ClientMessage>(&text) {
+                    Ok(msg) => handle_client(&state, &mut subscriptions, msg).await?,
+                    Err(e) => ServerMessage::Error {
+                        code: "invalid_message",
+                        message: e.to_string(),
+                    },
+                };
+                conn.send_json(&reply).await?;
+            }
+            Input::Client(Ok(Message::Close(_))) | Input::Client(Err(_)) => break,
+            Input::Client(Ok(_)) => {}
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,239 @@
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+use xactor::*;
+
+use crate::messages::*;
+
+///
+/// Upper bounds in seconds of the buckets of the fetch duration histograms
+///
+const FETCH_BUCKETS: &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+///
+/// Upper bounds in seconds of the buckets of the HTTP request duration histograms
+///
+const HTTP_BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+///
+/// Cumulative histogram of observed durations, as exported to Prometheus
+///
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    bounds: &'static [f64],
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    pub fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            counts: vec![0; bounds.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    pub fn observe(&mut self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        for (bound, count) in self.bounds.iter().zip(self.counts.iter_mut()) {
+            if secs <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += secs;
+        self.count += 1;
+    }
+
+    pub fn write(&self, out: &mut String, name: &str, labels: &[(&str, &str)]) {
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            let le = bound.to_string();
+            let mut bucket_labels = labels.to_vec();
+            bucket_labels.push(("le", &le));
+            write_sample(out, &format!("{}_bucket", name), &bucket_labels, *count);
+        }
+        let mut inf_labels = labels.to_vec();
+        inf_labels.push(("le", "+Inf"));
+        write_sample(out, &format!("{}_bucket", name), &inf_labels, self.count);
+        write_sample(out, &format!("{}_sum", name), labels, self.sum);
+        write_sample(out, &format!("{}_count", name), labels, self.count);
+    }
+}
+
+///
+/// Write the `# HELP` and `# TYPE` lines of a metric family
+///
+pub fn write_header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+///
+/// Write a single sample in the Prometheus text format
+///
+pub fn write_sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: impl ToString) {
+    out.push_str(name);
+    if !labels.is_empty() {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(key, value)| {
+                let value = value
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('\n', "\\n");
+                format!("{}=\"{}\"", key, value)
+            })
+            .collect();
+        let _ = write!(out, "{{{}}}", labels.join(","));
+    }
+    let _ = writeln!(out, " {}", value.to_string());
+}
+
+///
+/// Collects counters and histograms from the events flowing through the broker
+/// and from the requests served by the HTTP API
+///
+#[derive(Default)]
+pub struct Metrics {
+    fetch_duration: BTreeMap<String, Histogram>,
+    fetch_errors: BTreeMap<FetchErrorKind, u64>,
+    quotes_processed: u64,
+    indicators_written: u64,
+    http_requests: BTreeMap<(String, String, u16), u64>,
+    http_duration: BTreeMap<String, Histogram>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+
+        let name = "stock_tracker_fetch_duration_seconds";
+        write_header(
+            &mut out,
+            name,
+            "Time taken to fetch the quotes of a symbol",
+            "histogram",
+        );
+        for (symbol, histogram) in &self.fetch_duration {
+            histogram.write(&mut out, name, &[("symbol", symbol)]);
+        }
+
+        let name = "stock_tracker_fetch_errors_total";
+        write_header(&mut out, name, "Failed fetches by kind of error", "counter");
+        for (kind, count) in &self.fetch_errors {
+            write_sample(&mut out, name, &[("kind", kind.as_str())], count);
+        }
+
+        let name = "stock_tracker_quotes_processed_total";
+        write_header(
+            &mut out,
+            name,
+            "Quotes run through the processor",
+            "counter",
+        );
+        write_sample(&mut out, name, &[], self.quotes_processed);
+
+        let name = "stock_tracker_indicators_written_total";
+        write_header(
+            &mut out,
+            name,
+            "Indicators calculated and stored",
+            "counter",
+        );
+        write_sample(&mut out, name, &[], self.indicators_written);
+
+        let name = "stock_tracker_http_requests_total";
+        write_header(&mut out, name, "HTTP requests served", "counter");
+        for ((method, route, status), count) in &self.http_requests {
+            let status = status.to_string();
+            let labels = [
+                ("method", method.as_str()),
+                ("route", route),
+                ("status", &status),
+            ];
+            write_sample(&mut out, name, &labels, count);
+        }
+
+        let name = "stock_tracker_http_request_duration_seconds";
+        write_header(
+            &mut out,
+            name,
+            "Time taken to answer HTTP requests",
+            "histogram",
+        );
+        for (route, histogram) in &self.http_duration {
+            histogram.write(&mut out, name, &[("route", route)]);
+        }
+
+        out
+    }
+}
+
+#[async_trait]
+impl Actor for Metrics {
+    async fn started(&mut self, ctx: &mut Context<Self>) -> Result<()> {
+        ctx.subscribe::<FetchStatus>().await?;
+        ctx.subscribe::<SymbolProcessed>().await?;
+        ctx.subscribe::<Indicators>().await
+    }
+}
+
+#[async_trait]
+impl Handler<FetchStatus> for Metrics {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, msg: FetchStatus) {
+        self.fetch_duration
+            .entry(msg.symbol)
+            .or_insert_with(|| Histogram::new(FETCH_BUCKETS))
+            .observe(msg.duration);
+        if let Some(kind) = msg.error_kind {
+            *self.fetch_errors.entry(kind).or_default() += 1;
+        }
+    }
+}
+
+#[async_trait]
+impl Handler<SymbolProcessed> for Metrics {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _msg: SymbolProcessed) {
+        self.quotes_processed += 1;
+    }
+}
+
+#[async_trait]
+impl Handler<Indicators> for Metrics {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _msg: Indicators) {
+        self.indicators_written += 1;
+    }
+}
+
+#[async_trait]
+impl Handler<HttpRequestServed> for Metrics {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, msg: HttpRequestServed) {
+        self.http_duration
+            .entry(msg.route.clone())
+            .or_insert_with(|| Histogram::new(HTTP_BUCKETS))
+            .observe(msg.duration);
+        *self
+            .http_requests
+            .entry((msg.method, msg.route, msg.status))
+            .or_default() += 1;
+    }
+}
+
+#[async_trait]
+impl Handler<RenderMetrics> for Metrics {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _msg: RenderMetrics) -> String {
+        self.render()
+    }
+}
+
+#[async_trait]
+impl Handler<Ping> for Metrics {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _msg: Ping) {}
+}
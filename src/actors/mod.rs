@@ -1,5 +1,7 @@
 pub mod data_holder;
 pub mod fetcher;
 pub mod http_server;
+pub mod metrics;
 pub mod processor;
+pub mod scheduler;
 pub mod writer;
@@ -1,59 +1,178 @@
-use crate::signals::*;
 use async_trait::async_trait;
+use chrono::prelude::*;
+use std::collections::HashMap;
 use xactor::*;
 
+use super::writer;
 use crate::messages::*;
+use crate::registry::{Column, Indicator, IndicatorSet};
+use crate::streaming::*;
 
-pub struct Processor;
+pub struct Processor {
+    indicators: IndicatorSet,
+    columns: Vec<Column>,
+    symbols: HashMap<String, SymbolSignals>,
+}
+
+impl Processor {
+    ///
+    /// Processor computing the configured set of indicators for every symbol
+    ///
+    pub fn new(indicators: IndicatorSet) -> Self {
+        Self {
+            columns: indicators.columns(),
+            indicators,
+            symbols: HashMap::new(),
+        }
+    }
+
+    ///
+    /// Update the signals of a symbol with the bars of a quote they haven't seen yet
+    ///
+    /// # Returns
+    ///
+    /// The indicators as of the latest bar or `None` for a quote without bars
+    ///
+    pub fn process(&mut self, quote: &Quote) -> Option<Indicators> {
+        let (latest, complete) = quote.bars.split_last()?;
+
+        let indicators = &self.indicators;
+        let signals = self
+            .symbols
+            .entry(quote.symbol.clone())
+            .or_insert_with(|| SymbolSignals::new(quote.from, indicators));
+        // start over if the quote doesn't continue the bars the signals have seen
+        if signals.from != quote.from || signals.last.is_some_and(|last| latest.timestamp <= last) {
+            *signals = SymbolSignals::new(quote.from, indicators);
+        }
+
+        // the latest bar may still change until the next one starts,
+        // so only the bars before it are fed for good
+        for bar in complete {
+            if signals.last.is_none_or(|last| bar.timestamp > last) {
+                signals.update(quote, bar, &self.columns);
+                signals.last = Some(bar.timestamp);
+            }
+        }
+        Some(signals.peek(quote, latest, &self.columns))
+    }
+}
+
+///
+/// Signals of one symbol, updated with every bar of its quotes up to `last`
+///
+struct SymbolSignals {
+    from: DateTime<Utc>,
+    last: Option<DateTime<Utc>>,
+    price_difference: StreamingPriceDifference,
+    min: StreamingMinPrice,
+    max: StreamingMaxPrice,
+    indicators: Vec<Box<dyn Indicator>>,
+}
+
+impl SymbolSignals {
+    fn new(from: DateTime<Utc>, indicators: &IndicatorSet) -> Self {
+        Self {
+            from,
+            last: None,
+            price_difference: StreamingPriceDifference::default(),
+            min: StreamingMinPrice::default(),
+            max: StreamingMaxPrice::default(),
+            indicators: indicators.build(),
+        }
+    }
+
+    ///
+    /// Update every signal with the next bar of a quote
+    ///
+    /// # Returns
+    ///
+    /// The indicators as of that bar, with a value for each of `columns`
+    ///
+    fn update(&mut self, quote: &Quote, bar: &Bar, columns: &[Column]) -> Indicators {
+        let price_difference = self.price_difference.update(bar);
+        let min = self.min.update(bar);
+        let max = self.max.update(bar);
+        let values = self.indicators.iter_mut().flat_map(|i| i.update(bar));
+        indicators(quote, bar, price_difference, min, max, columns, values)
+    }
+
+    ///
+    /// The indicators as of the next bar of a quote, without updating the signals with it
+    ///
+    fn peek(&self, quote: &Quote, bar: &Bar, columns: &[Column]) -> Indicators {
+        let price_difference = self.price_difference.peek(bar);
+        let min = self.min.peek(bar);
+        let max = self.max.peek(bar);
+        let values = self.indicators.iter().flat_map(|i| i.peek(bar));
+        indicators(quote, bar, price_difference, min, max, columns, values)
+    }
+}
+
+fn indicators(
+    quote: &Quote,
+    bar: &Bar,
+    price_difference: Option<(f64, f64)>,
+    min: Option<f64>,
+    max: Option<f64>,
+    columns: &[Column],
+    values: impl Iterator<Item = Option<f64>>,
+) -> Indicators {
+    let (_, pct_change) = price_difference.unwrap_or((0.0, 0.0));
+    Indicators {
+        symbol: quote.symbol.clone(),
+        from: quote.from,
+        timestamp: bar.timestamp,
+        last_price: bar.adjclose,
+        pct_change,
+        period_min: min.unwrap_or(bar.adjclose),
+        period_max: max.unwrap_or(bar.adjclose),
+        values: columns
+            .iter()
+            .zip(values)
+            .map(|(c, value)| (c.name.clone(), value.map(|v| c.unit.value(v))))
+            .collect(),
+    }
+}
 
 #[async_trait]
 impl Actor for Processor {
     async fn started(&mut self, ctx: &mut Context<Self>) -> Result<()> {
-        println!("period start,symbol,price,change %,min,max,30d avg");
-        ctx.subscribe::<Quote>().await
+        println!("{}", writer::header(&self.columns));
+        ctx.subscribe::<Quote>().await?;
+        ctx.subscribe::<SymbolRemoved>().await
     }
 }
 
 #[async_trait]
 impl Handler<Quote> for Processor {
     async fn handle(&mut self, _ctx: &mut Context<Self>, msg: Quote) {
-        if msg.series.is_empty() {
-            return ();
+        let indicators = self.process(&msg);
+        if let Some(indicators) = &indicators {
+            println!("{}", writer::format_line(indicators, &self.columns));
+            let _ = Broker::from_registry()
+                .await
+                .unwrap()
+                .publish(indicators.clone());
         }
 
-        let period_max: f64 = MaxPrice {}.calculate(&msg.series).await.unwrap();
-        let period_min: f64 = MinPrice {}.calculate(&msg.series).await.unwrap();
-        let last_price = *msg.series.last().unwrap_or(&0.0);
-        let (_, pct_change) = PriceDifference {}
-            .calculate(&msg.series)
-            .await
-            .unwrap_or((0.0, 0.0));
-        let mut sma = WindowedSMA { window_size: 30 }
-            .calculate(&msg.series)
-            .await
-            .unwrap_or_default();
-
-        let indicators = Indicators {
-            symbol: msg.symbol.clone(),
-            from: msg.from,
-            last_price,
-            pct_change,
-            period_min,
-            period_max,
-            last_sma: sma.pop().unwrap_or(0.0),
+        let processed = SymbolProcessed {
+            cycle: msg.cycle,
+            symbol: msg.symbol,
+            indicators,
         };
+        let _ = Broker::from_registry().await.unwrap().publish(processed);
+    }
+}
 
-        let _ = Broker::from_registry().await.unwrap().publish(indicators);
-
-        println!(
-            "{},{},${:.2},{:.2}%,${:.2},${:.2},${:.2}",
-            msg.from.to_rfc3339(),
-            msg.symbol,
-            last_price,
-            pct_change * 100.0,
-            period_min,
-            period_max,
-            sma.last().unwrap_or(&0.0)
-        );
+#[async_trait]
+impl Handler<SymbolRemoved> for Processor {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, msg: SymbolRemoved) {
+        self.symbols.remove(&msg.0);
     }
 }
+
+#[async_trait]
+impl Handler<Ping> for Processor {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _msg: Ping) {}
+}
@@ -0,0 +1,392 @@
+use async_std::channel::{self, Sender};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::time::Duration;
+use xactor::*;
+
+use crate::messages::*;
+
+///
+/// Longest a fetch cycle may take before it is completed
+/// with whatever symbols have reported back so far
+///
+const CYCLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+///
+/// Number of refresh jobs kept around for polling
+///
+const MAX_JOBS: usize = 100;
+
+///
+/// A fetch cycle that has been published and not completed yet
+///
+struct Cycle {
+    id: u64,
+    started_at: DateTime<Utc>,
+    symbols: usize,
+    failed: usize,
+    pending: HashSet<String>,
+    jobs: Vec<u64>,
+}
+
+///
+/// Requests for a fetch cycle that arrived while another one was running.
+/// They are coalesced into a single cycle started as soon as the running one completes.
+///
+#[derive(Default)]
+struct QueuedCycle {
+    symbols: BTreeSet<String>,
+    jobs: Vec<u64>,
+}
+
+///
+/// Owns the set of tracked symbols and triggers a fetch cycle at a fixed interval.
+///
+/// Only one cycle runs at a time: ticks and refresh requests arriving while a
+/// cycle is running are merged into one queued cycle instead of overlapping it.
+///
+pub struct Scheduler {
+    symbols: BTreeSet<String>,
+    from: DateTime<Utc>,
+    interval: Duration,
+    next_cycle: u64,
+    running: Option<Cycle>,
+    queued: Option<QueuedCycle>,
+    next_job: u64,
+    jobs: BTreeMap<u64, RefreshJob>,
+    waiters: HashMap<u64, Vec<Sender<RefreshJob>>>,
+    cycles_completed: u64,
+    fetch_errors: u64,
+    last_cycle: Option<CycleSummary>,
+    last_successful_cycle: Option<CycleSummary>,
+}
+
+impl Scheduler {
+    pub fn new(symbols: Vec<String>, from: DateTime<Utc>, interval: Duration) -> Self {
+        Self {
+            symbols: symbols.into_iter().collect(),
+            from,
+            interval,
+            next_cycle: 1,
+            running: None,
+            queued: None,
+            next_job: 1,
+            jobs: BTreeMap::new(),
+            waiters: HashMap::new(),
+            cycles_completed: 0,
+            fetch_errors: 0,
+            last_cycle: None,
+            last_successful_cycle: None,
+        }
+    }
+
+    fn symbols(&self) -> Vec<String> {
+        self.symbols.iter().cloned().collect()
+    }
+
+    ///
+    /// Start a cycle right away or queue it behind the running one
+    ///
+    async fn request_cycle(
+        &mut self,
+        ctx: &mut Context<Self>,
+        symbols: Vec<String>,
+        job: Option<u64>,
+    ) {
+        if self.running.is_some() {
+            let queued = self.queued.get_or_insert_with(QueuedCycle::default);
+            queued.symbols.extend(symbols);
+            queued.jobs.extend(job);
+        } else {
+            self.start_cycle(ctx, symbols, job.into_iter().collect())
+                .await;
+        }
+    }
+
+    async fn start_cycle(&mut self, ctx: &mut Context<Self>, symbols: Vec<String>, jobs: Vec<u64>) {
+        let id = self.next_cycle;
+        self.next_cycle += 1;
+
+        for job in &jobs {
+            if let Some(job) = self.jobs.get_mut(job) {
+                job.state = JobState::Running;
+            }
+        }
+        self.running = Some(Cycle {
+            id,
+            started_at: Utc::now(),
+            symbols: symbols.len(),
+            failed: 0,
+            pending: symbols.iter().cloned().collect(),
+            jobs,
+        });
+
+        // a cycle without symbols has nothing to wait for
+        if symbols.is_empty() {
+            ctx.send_later(CycleTimeout(id), Duration::from_secs(0));
+            return;
+        }
+
+        ctx.send_later(CycleTimeout(id), CYCLE_TIMEOUT);
+        let fetch_quote = FetchQuotes {
+            cycle: id,
+            symbols,
+            from: self.from,
+            to: Utc::now(),
+        };
+        let _ = Broker::from_registry().await.unwrap().publish(fetch_quote);
+    }
+
+    ///
+    /// Record the outcome for one symbol of the running cycle
+    ///
+    async fn finish_symbol(
+        &mut self,
+        ctx: &mut Context<Self>,
+        cycle: u64,
+        symbol: &str,
+        outcome: std::result::Result<Option<Indicators>, String>,
+    ) {
+        let running = match &mut self.running {
+            Some(running) if running.id == cycle => running,
+            _ => return,
+        };
+        if !running.pending.remove(symbol) {
+            return;
+        }
+        if outcome.is_err() {
+            running.failed += 1;
+            self.fetch_errors += 1;
+        }
+
+        for job in &running.jobs {
+            if let Some(job) = self.jobs.get_mut(job) {
+                if !job.symbols.iter().any(|s| s == symbol) {
+                    continue;
+                }
+                match &outcome {
+                    Ok(Some(indicators)) => job.indicators.push(indicators.clone()),
+                    Ok(None) => {}
+                    Err(error) => {
+                        job.errors.insert(symbol.to_string(), error.clone());
+                    }
+                }
+            }
+        }
+
+        if running.pending.is_empty() {
+            self.complete_cycle(ctx).await;
+        }
+    }
+
+    ///
+    /// Finish the running cycle, hand the results to waiting refresh requests
+    /// and start the queued cycle, if any
+    ///
+    async fn complete_cycle(&mut self, ctx: &mut Context<Self>) {
+        let running = match self.running.take() {
+            Some(running) => running,
+            None => return,
+        };
+
+        // a cycle succeeded if at least one of its symbols was fetched
+        let summary = CycleSummary {
+            id: running.id,
+            started_at: running.started_at,
+            completed_at: Utc::now(),
+            symbols: running.symbols,
+            failed: running.failed,
+        };
+        if summary.failed < summary.symbols {
+            self.last_successful_cycle = Some(summary.clone());
+        }
+        self.last_cycle = Some(summary);
+        self.cycles_completed += 1;
+
+        for id in running.jobs {
+            if let Some(job) = self.jobs.get_mut(&id) {
+                job.state = JobState::Done;
+                job.completed_at = Some(Utc::now());
+                for waiter in self.waiters.remove(&id).unwrap_or_default() {
+                    let _ = waiter.try_send(job.clone());
+                }
+            }
+        }
+
+        // queued and running jobs are still being polled, so only finished ones are pruned
+        let excess = self.jobs.len().saturating_sub(MAX_JOBS);
+        let pruned: Vec<u64> = self
+            .jobs
+            .values()
+            .filter(|job| job.state == JobState::Done)
+            .map(|job| job.id)
+            .take(excess)
+            .collect();
+        for id in pruned {
+            self.jobs.remove(&id);
+        }
+
+        if let Some(queued) = self.queued.take() {
+            let symbols = queued.symbols.into_iter().collect();
+            self.start_cycle(ctx, symbols, queued.jobs).await;
+        }
+    }
+}
+
+#[async_trait]
+impl Actor for Scheduler {
+    async fn started(&mut self, ctx: &mut Context<Self>) -> Result<()> {
+        ctx.send_interval(ScheduleFetch, self.interval);
+        ctx.subscribe::<FetchStatus>().await?;
+        ctx.subscribe::<SymbolProcessed>().await
+    }
+}
+
+#[async_trait]
+impl Handler<ScheduleFetch> for Scheduler {
+    async fn handle(&mut self, ctx: &mut Context<Self>, _msg: ScheduleFetch) {
+        let symbols = self.symbols();
+        self.request_cycle(ctx, symbols, None).await;
+    }
+}
+
+#[async_trait]
+impl Handler<FetchStatus> for Scheduler {
+    async fn handle(&mut self, ctx: &mut Context<Self>, msg: FetchStatus) {
+        // successful fetches are finished once the processor is done with them
+        if let Some(error) = msg.error {
+            self.finish_symbol(ctx, msg.cycle, &msg.symbol, Err(error))
+                .await;
+        }
+    }
+}
+
+#[async_trait]
+impl Handler<SymbolProcessed> for Scheduler {
+    async fn handle(&mut self, ctx: &mut Context<Self>, msg: SymbolProcessed) {
+        self.finish_symbol(ctx, msg.cycle, &msg.symbol, Ok(msg.indicators))
+            .await;
+    }
+}
+
+#[async_trait]
+impl Handler<CycleTimeout> for Scheduler {
+    async fn handle(&mut self, ctx: &mut Context<Self>, msg: CycleTimeout) {
+        let pending: Vec<String> = match &self.running {
+            Some(running) if running.id == msg.0 => running.pending.iter().cloned().collect(),
+            _ => return,
+        };
+
+        for symbol in pending {
+            let error = "timed out waiting for quotes".to_string();
+            self.finish_symbol(ctx, msg.0, &symbol, Err(error)).await;
+        }
+
+        // finishing the last symbol already completed the cycle unless it had none
+        if self.running.as_ref().map(|r| r.id) == Some(msg.0) {
+            self.complete_cycle(ctx).await;
+        }
+    }
+}
+
+#[async_trait]
+impl Handler<Refresh> for Scheduler {
+    async fn handle(
+        &mut self,
+        ctx: &mut Context<Self>,
+        msg: Refresh,
+    ) -> (RefreshJob, channel::Receiver<RefreshJob>) {
+        let symbols = if msg.0.is_empty() {
+            self.symbols()
+        } else {
+            // a symbol requested twice is fetched and counted once
+            msg.0
+                .into_iter()
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect()
+        };
+
+        let id = self.next_job;
+        self.next_job += 1;
+        self.jobs.insert(
+            id,
+            RefreshJob {
+                id,
+                state: JobState::Queued,
+                symbols: symbols.clone(),
+                requested_at: Utc::now(),
+                completed_at: None,
+                indicators: vec![],
+                errors: BTreeMap::new(),
+            },
+        );
+
+        let (sender, receiver) = channel::bounded(1);
+        self.waiters.entry(id).or_default().push(sender);
+        self.request_cycle(ctx, symbols, Some(id)).await;
+
+        (self.jobs[&id].clone(), receiver)
+    }
+}
+
+#[async_trait]
+impl Handler<GetRefreshJob> for Scheduler {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, msg: GetRefreshJob) -> Option<RefreshJob> {
+        self.jobs.get(&msg.0).cloned()
+    }
+}
+
+#[async_trait]
+impl Handler<AddSymbols> for Scheduler {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, msg: AddSymbols) -> Vec<String> {
+        self.symbols.extend(msg.0);
+        self.symbols()
+    }
+}
+
+#[async_trait]
+impl Handler<RemoveSymbol> for Scheduler {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, msg: RemoveSymbol) -> bool {
+        let removed = self.symbols.remove(&msg.0);
+        if removed {
+            let _ = Broker::from_registry()
+                .await
+                .unwrap()
+                .publish(SymbolRemoved(msg.0));
+        }
+        removed
+    }
+}
+
+#[async_trait]
+impl Handler<GetTrackedSymbols> for Scheduler {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _msg: GetTrackedSymbols) -> Vec<String> {
+        self.symbols()
+    }
+}
+
+#[async_trait]
+impl Handler<GetSchedulerStatus> for Scheduler {
+    async fn handle(
+        &mut self,
+        _ctx: &mut Context<Self>,
+        _msg: GetSchedulerStatus,
+    ) -> SchedulerStatus {
+        SchedulerStatus {
+            interval_secs: self.interval.as_secs(),
+            running_cycle: self.running.as_ref().map(|r| r.id),
+            cycle_queued: self.queued.is_some(),
+            cycles_completed: self.cycles_completed,
+            fetch_errors: self.fetch_errors,
+            last_cycle: self.last_cycle.clone(),
+            last_successful_cycle: self.last_successful_cycle.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl Handler<Ping> for Scheduler {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _msg: Ping) {}
+}
@@ -4,21 +4,61 @@ use async_trait::async_trait;
 use xactor::*;
 
 use crate::messages::*;
+use crate::registry::Column;
 
 pub struct Writer {
     filename: String,
+    columns: Vec<Column>,
     file: Option<File>,
 }
 
 impl Writer {
-    pub fn new(filename: String) -> Self {
+    ///
+    /// Writer of a human readable CSV with the given indicator columns
+    ///
+    pub fn new(filename: String, columns: Vec<Column>) -> Self {
         Self {
             filename,
+            columns,
             file: None,
         }
     }
 }
 
+///
+/// Header of the human readable CSV the tracker prints and writes
+///
+pub fn header(columns: &[Column]) -> String {
+    let mut header = String::from("period start,symbol,price,change %,min,max");
+    for column in columns {
+        header.push(',');
+        header.push_str(&column.name);
+    }
+    header
+}
+
+///
+/// Human readable CSV line of indicators, with a field for each of `columns`
+///
+pub fn format_line(i: &Indicators, columns: &[Column]) -> String {
+    let mut line = format!(
+        "{},{},${:.2},{:.2}%,${:.2},${:.2}",
+        i.from.to_rfc3339(),
+        i.symbol,
+        i.last_price,
+        i.pct_change * 100.0,
+        i.period_min,
+        i.period_max,
+    );
+    for column in columns {
+        line.push(',');
+        if let Some(value) = i.values.get(&column.name).copied().flatten() {
+            line.push_str(&column.unit.format(value));
+        }
+    }
+    line
+}
+
 #[async_trait]
 impl Actor for Writer {
     async fn started(&mut self, ctx: &mut Context<Self>) -> Result<()> {
@@ -29,8 +69,7 @@ impl Actor for Writer {
             .await
             .unwrap();
 
-        let header = b"period start,symbol,price,change %,min,max,30d avg";
-        let _ = file.write(header).await;
+        let _ = file.write(header(&self.columns).as_bytes()).await;
         self.file = Some(file);
         ctx.subscribe::<Indicators>().await
     }
@@ -40,18 +79,13 @@ impl Actor for Writer {
 impl Handler<Indicators> for Writer {
     async fn handle(&mut self, _ctx: &mut Context<Self>, msg: Indicators) {
         if let Some(mut file) = self.file.clone() {
-            let s = format!(
-                "\n{},{},${:.2},{:.2}%,${:.2},${:.2},${:.2}",
-                msg.from.to_rfc3339(),
-                msg.symbol,
-                msg.last_price,
-                msg.pct_change * 100.0,
-                msg.period_min,
-                msg.period_max,
-                msg.last_sma,
-            );
-
+            let s = format!("\n{}", format_line(&msg, &self.columns));
             let _ = file.write(s.as_bytes()).await;
         }
     }
 }
+
+#[async_trait]
+impl Handler<Ping> for Writer {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _msg: Ping) {}
+}
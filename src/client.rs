@@ -0,0 +1,158 @@
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::de::DeserializeOwned;
+use std::io::{Error, ErrorKind, Result};
+use surf::http::Method;
+use surf::Url;
+
+use crate::messages::*;
+use crate::{Command, SymbolsCommand};
+
+///
+/// Status and body of an HTTP response
+///
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+impl HttpResponse {
+    ///
+    /// Parse the JSON body, turning API error responses into an `Error`
+    ///
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        if self.status >= 400 {
+            let message = serde_json::from_str::<serde_json::Value>(&self.body)
+                .ok()
+                .and_then(|v| v["error"]["message"].as_str().map(String::from))
+                .unwrap_or_else(|| self.body.clone());
+            return Err(Error::other(format!(
+                "server responded with {}: {}",
+                self.status, message
+            )));
+        }
+        Ok(serde_json::from_str(&self.body)?)
+    }
+}
+
+///
+/// Path of a single symbol, percent-encoded so symbols like `^GSPC` or `EURUSD=X` survive
+///
+pub fn symbol_path(symbol: &str) -> String {
+    format!("/symbols/{}", utf8_percent_encode(symbol, NON_ALPHANUMERIC))
+}
+
+///
+/// URL of `path` below a base URL like `http://127.0.0.1:8080` or `https://tracker.example/api`
+///
+fn url(server: &str, path: &str) -> Result<Url> {
+    let invalid = |reason: String| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("'{}' is not an http(s) URL, {}", server, reason),
+        )
+    };
+    let url = Url::parse(&format!("{}{}", server.trim_end_matches('/'), path))
+        .map_err(|e| invalid(e.to_string()))?;
+    match url.scheme() {
+        "http" | "https" => Ok(url),
+        scheme => Err(invalid(format!("the scheme is {}", scheme))),
+    }
+}
+
+///
+/// Send a single request to the tracker's HTTP API
+///
+pub async fn request(
+    server: &str,
+    token: Option<&str>,
+    method: Method,
+    path: &str,
+    body: Option<serde_json::Value>,
+) -> Result<HttpResponse> {
+    let http_error = |e: surf::Error| Error::other(e.to_string());
+    let mut request = surf::Request::new(method, url(server, path)?);
+    request.insert_header("Accept", "application/json");
+    if let Some(token) = token {
+        // a token with whitespace or control characters could inject headers
+        if token.is_empty() || !token.bytes().all(|b| b.is_ascii_graphic()) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "the token must be printable ASCII without whitespace",
+            ));
+        }
+        request.insert_header("Authorization", format!("Bearer {}", token));
+    }
+    if let Some(body) = body {
+        request.body_json(&body).map_err(http_error)?;
+    }
+
+    let mut response = surf::client().send(request).await.map_err(http_error)?;
+    Ok(HttpResponse {
+        status: response.status().into(),
+        body: response.body_string().await.map_err(http_error)?,
+    })
+}
+
+fn print_symbols(symbols: &[SymbolStatus]) {
+    println!("symbol,status,last fetch,errors");
+    for s in symbols {
+        println!(
+            "{},{},{},{}",
+            s.symbol,
+            format!("{:?}", s.status).to_lowercase(),
+            s.last_fetch.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            s.error_count
+        );
+    }
+}
+
+///
+/// Run a client command against a running tracker
+///
+pub async fn run(command: Command) -> Result<()> {
+    match command {
+        Command::Symbols {
+            server,
+            token,
+            action,
+        } => match action {
+            SymbolsCommand::List => {
+                let res = request(&server, token.as_deref(), Method::Get, "/symbols", None).await?;
+                print_symbols(&res.json::<Vec<SymbolStatus>>()?);
+            }
+            SymbolsCommand::Add { symbols } => {
+                let body = serde_json::json!({ "symbols": symbols });
+                let res = request(
+                    &server,
+                    token.as_deref(),
+                    Method::Post,
+                    "/symbols",
+                    Some(body),
+                )
+                .await?;
+                print_symbols(&res.json::<Vec<SymbolStatus>>()?);
+            }
+            SymbolsCommand::Remove { symbol } => {
+                let path = symbol_path(&symbol);
+                let res = request(&server, token.as_deref(), Method::Delete, &path, None).await?;
+                if res.status >= 400 {
+                    res.json::<serde_json::Value>()?;
+                }
+                println!("Stopped tracking {}", symbol.to_uppercase());
+            }
+        },
+        Command::Refresh {
+            server,
+            token,
+            no_wait,
+            symbols,
+        } => {
+            let path = format!("/refresh?wait={}", !no_wait);
+            let body = serde_json::json!({ "symbols": symbols });
+            let res = request(&server, token.as_deref(), Method::Post, &path, Some(body)).await?;
+            let job: RefreshJob = res.json()?;
+            println!("{}", serde_json::to_string_pretty(&job)?);
+        }
+    }
+    Ok(())
+}
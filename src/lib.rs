@@ -0,0 +1,7 @@
+//!
+//! Messages and signals of the stock tracker, shared by the tracker and its benchmarks
+//!
+pub mod messages;
+pub mod registry;
+pub mod signals;
+pub mod streaming;
@@ -1,15 +1,17 @@
 mod actors;
-mod messages;
-mod signals;
+mod client;
+mod snapshot;
 #[cfg(test)]
 mod test;
 
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::time::Duration;
 
 use async_std::prelude::*;
-use async_std::stream;
 use chrono::prelude::*;
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use manning_lp_async_rust_project_1_m1::{messages, registry, streaming};
 use xactor::*;
 
 ///
@@ -17,6 +19,7 @@ use xactor::*;
 ///
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
+#[clap(subcommand_negates_reqs = true)]
 pub struct Args {
     ///
     /// Stock symbols. E.g: AAPL,MSFT,UBER,GOOG
@@ -27,55 +30,316 @@ pub struct Args {
     ///
     /// Date in the past to start fetching prices
     ///
-    #[clap(short, long)]
-    pub from: String,
+    #[clap(short, long, required = true)]
+    pub from: Option<String>,
+
+    ///
+    /// File to persist the collected indicators to, so they survive restarts
+    ///
+    #[clap(long, default_value = "data_holder.snapshot")]
+    pub snapshot_file: PathBuf,
+
+    ///
+    /// Seconds between two snapshots of the collected indicators
+    ///
+    #[clap(long, default_value_t = 60, parse(try_from_str = parse_interval))]
+    pub snapshot_interval: u64,
+
+    ///
+    /// Neither load nor save snapshots of the collected indicators
+    ///
+    #[clap(long)]
+    pub no_snapshot: bool,
+
+    ///
+    /// Annual return of a risk-free investment the Sharpe and Sortino ratios
+    /// are calculated against, e.g. 0.02 for 2%
+    ///
+    #[clap(long, default_value_t = 0.0)]
+    pub risk_free_rate: f64,
+
+    ///
+    /// Indicator to compute as `name` or `name:param,...`, e.g. sma:50, rsi:14 or bb:20,2,1
+    /// for bands of the sample deviation. Repeat the option to compute several,
+    /// left out parameters take their defaults.
+    ///
+    #[clap(
+        long = "indicator",
+        multiple_occurrences = true,
+        default_values = registry::DEFAULT_INDICATORS
+    )]
+    pub indicators: Vec<registry::IndicatorSpec>,
+
+    ///
+    /// Addresses to serve the HTTP API at, e.g. 0.0.0.0:8080 or [::1]:8080.
+    /// Repeat the option or separate addresses with commas to listen on several.
+    ///
+    #[clap(
+        long,
+        default_value = "127.0.0.1:8080",
+        multiple_occurrences = true,
+        use_value_delimiter = true
+    )]
+    pub listen: Vec<SocketAddr>,
+
+    ///
+    /// Don't serve the HTTP API at all
+    ///
+    #[clap(long, conflicts_with = "listen")]
+    pub no_http: bool,
+
+    ///
+    /// Bearer tokens allowed to read from the HTTP API.
+    /// Without any read or admin token, the API is open to everyone.
+    ///
+    #[clap(
+        long = "read-token",
+        env = "STOCK_TRACKER_READ_TOKENS",
+        multiple_occurrences = true,
+        use_value_delimiter = true,
+        hide_env_values = true
+    )]
+    pub read_tokens: Vec<String>,
+
+    ///
+    /// Bearer tokens allowed to read from the HTTP API and to change
+    /// the tracked symbols or trigger refreshes
+    ///
+    #[clap(
+        long = "admin-token",
+        env = "STOCK_TRACKER_ADMIN_TOKENS",
+        multiple_occurrences = true,
+        use_value_delimiter = true,
+        hide_env_values = true
+    )]
+    pub admin_tokens: Vec<String>,
+
+    ///
+    /// Origins of browser dashboards allowed to call the HTTP API, `*` for any
+    ///
+    #[clap(
+        long = "cors-origin",
+        env = "STOCK_TRACKER_CORS_ORIGINS",
+        multiple_occurrences = true,
+        use_value_delimiter = true
+    )]
+    pub cors_origins: Vec<String>,
+
+    ///
+    /// Talk to a running tracker instead of starting one
+    ///
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+///
+/// Client commands for a running tracker
+///
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    ///
+    /// Manage the tracked symbols
+    ///
+    Symbols {
+        ///
+        /// Base URL of the tracker's HTTP API
+        ///
+        #[clap(long, default_value = "http://127.0.0.1:8080")]
+        server: String,
+
+        ///
+        /// Bearer token to authenticate with
+        ///
+        #[clap(long, env = "STOCK_TRACKER_TOKEN", hide_env_values = true)]
+        token: Option<String>,
+
+        #[clap(subcommand)]
+        action: SymbolsCommand,
+    },
+
+    ///
+    /// Fetch quotes right away instead of waiting for the next cycle
+    ///
+    Refresh {
+        ///
+        /// Base URL of the tracker's HTTP API
+        ///
+        #[clap(long, default_value = "http://127.0.0.1:8080")]
+        server: String,
+
+        ///
+        /// Bearer token to authenticate with
+        ///
+        #[clap(long, env = "STOCK_TRACKER_TOKEN", hide_env_values = true)]
+        token: Option<String>,
+
+        ///
+        /// Return the job id instead of waiting for the cycle to complete
+        ///
+        #[clap(long)]
+        no_wait: bool,
+
+        ///
+        /// Symbols to refresh, all tracked symbols if none are given
+        ///
+        symbols: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SymbolsCommand {
+    ///
+    /// List the tracked symbols and their status
+    ///
+    List,
+
+    ///
+    /// Start tracking symbols
+    ///
+    Add {
+        #[clap(required = true)]
+        symbols: Vec<String>,
+    },
+
+    ///
+    /// Stop tracking a symbol
+    ///
+    Remove { symbol: String },
+}
+
+///
+/// Parse a number of seconds between two repetitions, which must not be zero
+///
+fn parse_interval(raw: &str) -> std::result::Result<u64, String> {
+    match raw.parse() {
+        Ok(0) => Err("must be at least 1 second".to_string()),
+        Ok(secs) => Ok(secs),
+        Err(e) => Err(e.to_string()),
+    }
 }
 
 #[xactor::main]
 async fn main() -> std::io::Result<()> {
     // Reading CLI args input
     let opts = Args::parse();
-    let from: DateTime<Utc> = opts.from.parse().expect("Couldn't parse 'from' date");
-    let symbols: Vec<String> = opts
-        .symbols
-        .split(',')
-        .map(|symbol| String::from(symbol))
-        .collect();
+    if let Some(command) = opts.command {
+        return client::run(command).await;
+    }
 
-    let output_file_name = format!("{}.csv", Utc::now().to_rfc2822());
+    let (shutdown, shutdown_requested) = async_std::channel::bounded(1);
+    ctrlc::set_handler(move || {
+        let _ = shutdown.try_send(());
+    })
+    .expect("Couldn't handle shutdown signals");
+
+    let from: DateTime<Utc> = opts
+        .from
+        .as_deref()
+        .unwrap_or_default()
+        .parse()
+        .expect("Couldn't parse 'from' date");
+    let symbols: Vec<String> = opts.symbols.split(',').map(String::from).collect();
 
-    let _fetcher_addr = actors::fetcher::Fetcher {}.start().await;
-    let _processor_addr = actors::processor::Processor {}.start().await;
-    let _writer_addr = actors::writer::Writer::new(output_file_name).start().await;
+    let output_file_name = format!("{}.csv", Utc::now().to_rfc2822());
 
-    let data_holder_addr = actors::data_holder::DataHolder::new()
-        .clone()
+    let fetcher_addr = actors::fetcher::Fetcher {}.start().await.unwrap();
+    let indicators = registry::IndicatorSet::new(opts.indicators.clone(), opts.risk_free_rate);
+    let columns = indicators.columns();
+    let processor_addr = actors::processor::Processor::new(indicators.clone())
         .start()
         .await
         .unwrap();
-
-    let _http_server_addr = actors::http_server::HttpServer::new(data_holder_addr)
+    let writer_addr = actors::writer::Writer::new(output_file_name, columns.clone())
         .start()
-        .await;
+        .await
+        .unwrap();
 
-    let _ = Broker::from_registry()
+    let mut data_holder = actors::data_holder::DataHolder::new();
+    if !opts.no_snapshot {
+        match snapshot::load(&opts.snapshot_file).await {
+            Ok(Some(snapshot)) => {
+                println!(
+                    "Restored {} indicators from snapshot saved at {}",
+                    snapshot.indicators.len(),
+                    snapshot.saved_at.to_rfc3339()
+                );
+                data_holder = data_holder.restore(snapshot);
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Ignoring snapshot {}: {}", opts.snapshot_file.display(), e),
+        }
+        data_holder = data_holder.with_snapshot(
+            opts.snapshot_file.clone(),
+            Duration::from_secs(opts.snapshot_interval),
+        );
+    }
+    let data_holder_addr = data_holder.start().await.unwrap();
+
+    let config = actors::http_server::health::ConfigSummary {
+        symbols: symbols.clone(),
+        from: Some(from),
+        snapshot_file: (!opts.no_snapshot).then(|| opts.snapshot_file.display().to_string()),
+        snapshot_interval_secs: (!opts.no_snapshot).then_some(opts.snapshot_interval),
+        listen: opts.listen.iter().map(|a| a.to_string()).collect(),
+        auth: !opts.read_tokens.is_empty() || !opts.admin_tokens.is_empty(),
+        cors_origins: opts.cors_origins.clone(),
+        indicators: indicators.specs().iter().map(|s| s.to_string()).collect(),
+        columns: columns.clone(),
+    };
+
+    let scheduler_addr = actors::scheduler::Scheduler::new(symbols, from, Duration::from_secs(30))
+        .start()
         .await
-        .unwrap()
-        .publish(messages::StartHttpServer(8080));
-
-    let mut interval = stream::interval(Duration::from_secs(30));
-
-    while let Some(_) = interval.next().await {
-        // Init the current moment
-        let to = Utc::now();
-        // Loop loop through the symbols
-        // and call the async function of calculation for each symbol
-        let fetch_quote = messages::FetchQuotes {
-            symbols: symbols.clone(),
-            from,
-            to,
-        };
-        let _ = Broker::from_registry().await.unwrap().publish(fetch_quote);
+        .unwrap();
+
+    if !opts.no_http {
+        let metrics_addr = actors::metrics::Metrics::new().start().await.unwrap();
+        let state = actors::http_server::AppState::new(
+            data_holder_addr.clone(),
+            scheduler_addr.clone(),
+            metrics_addr,
+        )
+        .with_config(config)
+        .with_tokens(actors::http_server::auth::Tokens::new(
+            opts.read_tokens.clone(),
+            opts.admin_tokens.clone(),
+        ))
+        .with_cors(opts.cors_origins.clone())
+        .with_actor("fetcher", &fetcher_addr)
+        .with_actor("processor", &processor_addr)
+        .with_actor("writer", &writer_addr);
+
+        let http_server_addr = actors::http_server::HttpServer::new(state)
+            .start()
+            .await
+            .unwrap();
+        match http_server_addr
+            .call(messages::StartHttpServer(opts.listen.clone()))
+            .await
+            .unwrap()
+        {
+            Ok(urls) => {
+                for url in urls {
+                    println!("Serving the HTTP API at {}", url);
+                }
+            }
+            Err(e) => {
+                eprintln!("Couldn't start the HTTP server, {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let interrupted = async {
+        let _ = shutdown_requested.recv().await;
+    };
+    scheduler_addr.wait_for_stop().race(interrupted).await;
+
+    // stopping the data holder saves a final snapshot
+    println!("Shutting down");
+    let mut data_holder_addr = data_holder_addr;
+    if data_holder_addr.stop(None).is_ok() {
+        data_holder_addr.wait_for_stop().await;
     }
 
     Ok(())
@@ -1,38 +1,344 @@
+use async_std::channel::Receiver;
 use chrono::prelude::*;
+use indexmap::IndexMap;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::time::Duration;
 use xactor::*;
 
+#[message]
+#[derive(Debug, Clone)]
+pub struct ScheduleFetch;
+
+#[message(result = "Vec<String>")]
+#[derive(Debug, Clone)]
+pub struct AddSymbols(pub Vec<String>);
+
+#[message(result = "bool")]
+#[derive(Debug, Clone)]
+pub struct RemoveSymbol(pub String);
+
+///
+/// Published once a symbol is no longer tracked, so its state can be dropped
+///
+#[message]
+#[derive(Debug, Clone)]
+pub struct SymbolRemoved(pub String);
+
+#[message(result = "Vec<String>")]
+#[derive(Debug, Clone)]
+pub struct GetTrackedSymbols;
+
 #[message]
 #[derive(Clone)]
 pub struct FetchQuotes {
+    pub cycle: u64,
     pub symbols: Vec<String>,
     pub from: DateTime<Utc>,
     pub to: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct Bar {
+    pub timestamp: DateTime<Utc>,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub adjclose: f64,
+    pub volume: u64,
+}
+
 #[message]
 #[derive(Clone)]
 pub struct Quote {
+    pub cycle: u64,
     pub symbol: String,
     pub from: DateTime<Utc>,
-    pub series: Vec<f64>,
+    pub bars: Vec<Bar>,
 }
 
 #[message]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone)]
+pub struct FetchStatus {
+    pub cycle: u64,
+    pub symbol: String,
+    pub fetched_at: DateTime<Utc>,
+    pub duration: Duration,
+    pub error: Option<String>,
+    pub error_kind: Option<FetchErrorKind>,
+}
+
+///
+/// Broad category of a failed fetch, used to label error metrics
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FetchErrorKind {
+    Connection,
+    Status,
+    Parse,
+    EmptyData,
+    Inconsistent,
+}
+
+impl FetchErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FetchErrorKind::Connection => "connection",
+            FetchErrorKind::Status => "status",
+            FetchErrorKind::Parse => "parse",
+            FetchErrorKind::EmptyData => "empty_data",
+            FetchErrorKind::Inconsistent => "inconsistent",
+        }
+    }
+}
+
+#[message]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Indicators {
     pub symbol: String,
     pub from: DateTime<Utc>,
+    pub timestamp: DateTime<Utc>,
     pub last_price: f64,
     pub pct_change: f64,
     pub period_min: f64,
     pub period_max: f64,
-    pub last_sma: f64,
+    ///
+    /// Values of the configured indicators by column, missing until enough bars were seen
+    ///
+    #[serde(flatten)]
+    pub values: IndexMap<String, Option<IndicatorValue>>,
 }
+
+///
+/// Value of an indicator column, a time for columns like `drawdown_peak`
+///
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum IndicatorValue {
+    Number(f64),
+    Time(DateTime<Utc>),
+}
+
+impl fmt::Display for IndicatorValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndicatorValue::Number(value) => write!(f, "{}", value),
+            IndicatorValue::Time(time) => write!(f, "{}", time.to_rfc3339()),
+        }
+    }
+}
+
+#[message]
+#[derive(Debug, Clone)]
+pub struct SymbolProcessed {
+    pub cycle: u64,
+    pub symbol: String,
+    pub indicators: Option<Indicators>,
+}
+
 #[message]
 #[derive(Debug, Clone)]
-pub struct StartHttpServer(pub u32);
+pub struct CycleTimeout(pub u64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Queued,
+    Running,
+    Done,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct RefreshJob {
+    pub id: u64,
+    pub state: JobState,
+    pub symbols: Vec<String>,
+    pub requested_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub indicators: Vec<Indicators>,
+    pub errors: BTreeMap<String, String>,
+}
+
+#[message(result = "(RefreshJob, Receiver<RefreshJob>)")]
+#[derive(Debug, Clone)]
+pub struct Refresh(pub Vec<String>);
+
+#[message(result = "Option<RefreshJob>")]
+#[derive(Debug, Clone)]
+pub struct GetRefreshJob(pub u64);
+
+#[message(result = "std::io::Result<Vec<String>>")]
+#[derive(Debug, Clone)]
+pub struct StartHttpServer(pub Vec<SocketAddr>);
 
 #[message(result = "Vec<Indicators>")]
 #[derive(Debug, Clone)]
 pub struct GetIndicators(pub usize);
+
+#[message]
+#[derive(Debug, Clone)]
+pub struct SaveSnapshot;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolState {
+    Pending,
+    Ok,
+    Failing,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct SymbolStatus {
+    pub symbol: String,
+    pub status: SymbolState,
+    pub last_fetch: Option<DateTime<Utc>>,
+    pub last_success: Option<DateTime<Utc>>,
+    pub error_count: u64,
+    pub last_error: Option<String>,
+}
+
+#[message(result = "Vec<SymbolStatus>")]
+#[derive(Debug, Clone)]
+pub struct GetSymbols(pub Vec<String>);
+
+#[message(result = "Option<Indicators>")]
+#[derive(Debug, Clone)]
+pub struct GetLatestIndicators(pub String);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    Asc,
+    #[default]
+    Desc,
+}
+
+///
+/// Query a page of the held indicators. `cursor` is the event id of the
+/// last indicators of the previous page, `offset` skips indicators after it.
+/// Fails with the first requested symbol that is unknown.
+///
+#[message(result = "std::result::Result<HistoryPage, String>")]
+#[derive(Debug, Clone)]
+pub struct GetHistory {
+    pub symbols: Vec<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub order: Order,
+    pub cursor: Option<u64>,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct HistoryPage {
+    pub indicators: Vec<Indicators>,
+    pub next_cursor: Option<u64>,
+}
+
+#[message(result = "Option<Vec<Bar>>")]
+#[derive(Debug, Clone)]
+pub struct GetBars(pub String);
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct IndicatorsEvent {
+    pub id: u64,
+    pub indicators: Indicators,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct Alert {
+    pub symbol: String,
+    pub at: DateTime<Utc>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum LiveEvent {
+    Indicators(Box<IndicatorsEvent>),
+    Alert(Alert),
+}
+
+impl LiveEvent {
+    pub fn symbol(&self) -> &str {
+        match self {
+            LiveEvent::Indicators(event) => &event.indicators.symbol,
+            LiveEvent::Alert(alert) => &alert.symbol,
+        }
+    }
+}
+
+///
+/// Indicators a resuming subscriber missed, oldest first
+///
+#[derive(Debug, Clone, Default)]
+pub struct Replay {
+    pub events: Vec<IndicatorsEvent>,
+    ///
+    /// Number of missed indicators too old to be replayed,
+    /// the subscriber has to fetch the history again if there are any
+    ///
+    pub dropped: u64,
+}
+
+#[message(result = "(Replay, Receiver<LiveEvent>)")]
+#[derive(Debug, Clone)]
+pub struct SubscribeLive {
+    pub last_event_id: Option<u64>,
+}
+
+#[message]
+#[derive(Debug, Clone)]
+pub struct Ping;
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct CycleSummary {
+    pub id: u64,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+    pub symbols: usize,
+    pub failed: usize,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct SchedulerStatus {
+    pub interval_secs: u64,
+    pub running_cycle: Option<u64>,
+    pub cycle_queued: bool,
+    pub cycles_completed: u64,
+    pub fetch_errors: u64,
+    pub last_cycle: Option<CycleSummary>,
+    pub last_successful_cycle: Option<CycleSummary>,
+}
+
+#[message(result = "SchedulerStatus")]
+#[derive(Debug, Clone)]
+pub struct GetSchedulerStatus;
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct StoreStats {
+    pub indicators: usize,
+    pub bars: usize,
+    pub subscribers: usize,
+}
+
+#[message(result = "StoreStats")]
+#[derive(Debug, Clone)]
+pub struct GetStoreStats;
+
+#[message]
+#[derive(Debug, Clone)]
+pub struct HttpRequestServed {
+    pub method: String,
+    pub route: String,
+    pub status: u16,
+    pub duration: Duration,
+}
+
+#[message(result = "String")]
+#[derive(Debug, Clone)]
+pub struct RenderMetrics;
@@ -0,0 +1,630 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::prelude::*;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::messages::{Bar, IndicatorValue};
+use crate::signals::{BollingerBands, Deviation, Drawdown, EmaSeed, TRADING_DAYS_PER_YEAR};
+use crate::streaming::*;
+
+///
+/// Indicators the tracker computes unless others are configured
+///
+pub const DEFAULT_INDICATORS: &[&str] = &[
+    "sma:30",
+    "ema:30",
+    "rsi:14",
+    "macd:12,26,9",
+    "bb:20,2",
+    "volatility",
+    "drawdown",
+    "sharpe",
+    "sortino",
+    "atr:14",
+    "stoch:14,3,3",
+    "willr:14",
+    "obv",
+    "vwap",
+    "volume_sma:20",
+    "rvol:20",
+];
+
+///
+/// How the values of a column are shown to people
+///
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Unit {
+    Price,
+    Percent,
+    Score,
+    Ratio,
+    Volume,
+    ///
+    /// A time, computed as seconds since the Unix epoch
+    ///
+    Time,
+}
+
+impl Unit {
+    ///
+    /// Value of a column with this unit, turning the seconds of a time into one
+    ///
+    pub fn value(&self, value: f64) -> IndicatorValue {
+        match self {
+            Unit::Time => IndicatorValue::Time(Utc.timestamp(value as i64, 0)),
+            _ => IndicatorValue::Number(value),
+        }
+    }
+
+    pub fn format(&self, value: IndicatorValue) -> String {
+        let value = match value {
+            IndicatorValue::Number(value) => value,
+            IndicatorValue::Time(time) => return time.to_rfc3339(),
+        };
+        match self {
+            Unit::Price => format!("${:.2}", value),
+            Unit::Percent => format!("{:.2}%", value * 100.0),
+            Unit::Score => format!("{:.2}", value),
+            Unit::Ratio => format!("{:.4}", value),
+            Unit::Volume => format!("{:.0}", value),
+            Unit::Time => self.format(self.value(value)),
+        }
+    }
+}
+
+///
+/// A value computed for every bar, named after the indicator and its parameters,
+/// e.g. `sma_50` or `bb_20_2.5_0_upper`. With the default parameters the columns
+/// keep the names the fields of `Indicators` had, e.g. `last_sma` or `bollinger_upper`.
+///
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Column {
+    pub name: String,
+    pub unit: Unit,
+}
+
+///
+/// A configured signal, fed bar by bar and yielding one value per column
+///
+pub trait Indicator: Send {
+    fn update(&mut self, bar: &Bar) -> Vec<Option<f64>>;
+    ///
+    /// The values as if the bar was added, without adding it
+    ///
+    fn peek(&self, bar: &Bar) -> Vec<Option<f64>>;
+    fn clone_box(&self) -> Box<dyn Indicator>;
+}
+
+impl Clone for Box<dyn Indicator> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+///
+/// Splits the output of a streaming signal into the values of its columns
+///
+struct Columns<S: StreamingSignal> {
+    signal: S,
+    values: fn(Option<S::Output>) -> Vec<Option<f64>>,
+}
+
+impl<S: StreamingSignal + Clone> Clone for Columns<S> {
+    fn clone(&self) -> Self {
+        Self {
+            signal: self.signal.clone(),
+            values: self.values,
+        }
+    }
+}
+
+impl<S> Indicator for Columns<S>
+where
+    S: StreamingSignal + Clone + Send + 'static,
+{
+    fn update(&mut self, bar: &Bar) -> Vec<Option<f64>> {
+        (self.values)(self.signal.update(bar))
+    }
+
+    fn peek(&self, bar: &Bar) -> Vec<Option<f64>> {
+        (self.values)(self.signal.peek(bar))
+    }
+
+    fn clone_box(&self) -> Box<dyn Indicator> {
+        Box::new(self.clone())
+    }
+}
+
+fn single<S>(signal: S) -> Box<dyn Indicator>
+where
+    S: StreamingSignal<Output = f64> + Clone + Send + 'static,
+{
+    Box::new(Columns {
+        signal,
+        values: |value| vec![value],
+    })
+}
+
+///
+/// Max drawdown with the times of its peak and trough,
+/// which the signal only knows by the number of bars before them
+///
+#[derive(Clone, Default)]
+struct DrawdownTimes {
+    signal: StreamingMaxDrawdown,
+    bars: usize,
+    ///
+    /// Time of the highest price so far, the peak of any deeper drawdown to come
+    ///
+    peak: Option<f64>,
+    deepest: Option<(f64, f64)>,
+}
+
+impl DrawdownTimes {
+    ///
+    /// Times of the peak and trough of the drawdown as of the next bar
+    ///
+    fn times(&self, drawdown: Option<Drawdown>, bar: &Bar) -> Option<(f64, f64)> {
+        let drawdown = drawdown?;
+        if drawdown.trough != self.bars {
+            return self.deepest;
+        }
+        let time = bar.timestamp.timestamp() as f64;
+        let peak = if drawdown.peak == self.bars {
+            Some(time)
+        } else {
+            self.peak
+        };
+        Some((peak?, time))
+    }
+
+    fn values(drawdown: Option<Drawdown>, times: Option<(f64, f64)>) -> Vec<Option<f64>> {
+        vec![
+            drawdown.map(|d| d.depth),
+            times.map(|(peak, _)| peak),
+            times.map(|(_, trough)| trough),
+        ]
+    }
+}
+
+impl Indicator for DrawdownTimes {
+    fn update(&mut self, bar: &Bar) -> Vec<Option<f64>> {
+        let drawdown = self.signal.update(bar);
+        let times = self.times(drawdown, bar);
+        if self.signal.peak() == Some(self.bars) {
+            self.peak = Some(bar.timestamp.timestamp() as f64);
+        }
+        self.deepest = times;
+        self.bars += 1;
+        Self::values(drawdown, times)
+    }
+
+    fn peek(&self, bar: &Bar) -> Vec<Option<f64>> {
+        let drawdown = self.signal.peek(bar);
+        Self::values(drawdown, self.times(drawdown, bar))
+    }
+
+    fn clone_box(&self) -> Box<dyn Indicator> {
+        Box::new(self.clone())
+    }
+}
+
+///
+/// A kind of indicator that can be configured by name
+///
+struct Entry {
+    name: &'static str,
+    ///
+    /// Names and defaults of the parameters in the order they are given
+    ///
+    params: &'static [(&'static str, f64)],
+    ///
+    /// Suffixes, names with the default parameters and units of the columns,
+    /// an empty suffix for the column named after the indicator
+    ///
+    columns: &'static [(&'static str, &'static str, Unit)],
+    validate: fn(&[f64]) -> Result<(), String>,
+    build: fn(&[f64], f64) -> Box<dyn Indicator>,
+}
+
+///
+/// Longest window an indicator can be configured with, far more bars than a quote holds
+///
+const MAX_WINDOW: usize = 10_000;
+
+///
+/// Check that every parameter is a whole number of at least `min` bars,
+/// the fewest the signal yields values with, and at most `MAX_WINDOW`
+///
+fn windows(params: &[f64], min: usize) -> Result<(), String> {
+    let invalid = |p: &f64| p.fract() != 0.0 || *p < min as f64 || *p > MAX_WINDOW as f64;
+    match params.iter().find(|p| invalid(p)) {
+        Some(p) => Err(format!(
+            "{} is not a whole number of {} to {} bars",
+            p, min, MAX_WINDOW
+        )),
+        None => Ok(()),
+    }
+}
+
+fn none(_: &[f64]) -> Result<(), String> {
+    Ok(())
+}
+
+static REGISTRY: &[Entry] = &[
+    Entry {
+        name: "sma",
+        params: &[("window", 30.0)],
+        columns: &[("", "last_sma", Unit::Price)],
+        validate: |p| windows(p, 2),
+        build: |p, _| single(StreamingSMA::new(p[0] as usize)),
+    },
+    Entry {
+        name: "ema",
+        params: &[("span", 30.0)],
+        columns: &[("", "last_ema", Unit::Price)],
+        validate: |p| windows(p, 1),
+        build: |p, _| {
+            let span = p[0] as usize;
+            single(StreamingEMA::with_span(span).seeded(EmaSeed::Sma(span)))
+        },
+    },
+    Entry {
+        name: "rsi",
+        params: &[("period", 14.0)],
+        columns: &[("", "rsi_14", Unit::Score)],
+        validate: |p| windows(p, 1),
+        build: |p, _| single(StreamingRSI::new(p[0] as usize)),
+    },
+    Entry {
+        name: "macd",
+        params: &[("fast", 12.0), ("slow", 26.0), ("signal", 9.0)],
+        columns: &[
+            ("", "macd", Unit::Ratio),
+            ("signal", "macd_signal", Unit::Ratio),
+            ("histogram", "macd_histogram", Unit::Ratio),
+        ],
+        validate: |p| {
+            windows(p, 1)?;
+            if p[0] < p[1] {
+                Ok(())
+            } else {
+                Err("the fast span must be shorter than the slow one".to_string())
+            }
+        },
+        build: |p, _| {
+            Box::new(Columns {
+                signal: StreamingMACD::new(p[0] as usize, p[1] as usize, p[2] as usize),
+                values: |m| match m {
+                    Some(m) => vec![Some(m.macd), Some(m.signal), Some(m.histogram)],
+                    None => vec![None; 3],
+                },
+            })
+        },
+    },
+    Entry {
+        name: "bb",
+        // `sample` is 1 to use the sample standard deviation instead of the population one
+        params: &[("window", 20.0), ("k", 2.0), ("sample", 0.0)],
+        columns: &[
+            ("upper", "bollinger_upper", Unit::Price),
+            ("middle", "bollinger_middle", Unit::Price),
+            ("lower", "bollinger_lower", Unit::Price),
+            ("percent_b", "bollinger_percent_b", Unit::Score),
+            ("bandwidth", "bollinger_bandwidth", Unit::Ratio),
+        ],
+        validate: |p| {
+            windows(&p[..1], 2)?;
+            if p[1] < 0.0 {
+                Err("k must not be negative".to_string())
+            } else if p[2] != 0.0 && p[2] != 1.0 {
+                Err("sample must be 0 or 1".to_string())
+            } else {
+                Ok(())
+            }
+        },
+        build: |p, _| {
+            let deviation = if p[2] == 1.0 {
+                Deviation::Sample
+            } else {
+                Deviation::Population
+            };
+            let bands = BollingerBands::new(p[0] as usize, p[1], deviation);
+            Box::new(Columns {
+                signal: StreamingBollingerBands::new(bands),
+                values: |b| match b {
+                    Some(b) => vec![
+                        Some(b.upper),
+                        Some(b.middle),
+                        Some(b.lower),
+                        Some(b.percent_b),
+                        Some(b.bandwidth),
+                    ],
+                    None => vec![None; 5],
+                },
+            })
+        },
+    },
+    Entry {
+        name: "volatility",
+        params: &[],
+        columns: &[("", "realized_volatility", Unit::Percent)],
+        validate: none,
+        build: |_, _| single(StreamingRealizedVolatility::new(TRADING_DAYS_PER_YEAR)),
+    },
+    Entry {
+        name: "drawdown",
+        params: &[],
+        columns: &[
+            ("", "max_drawdown", Unit::Percent),
+            ("peak", "drawdown_peak", Unit::Time),
+            ("trough", "drawdown_trough", Unit::Time),
+        ],
+        validate: none,
+        build: |_, _| Box::new(DrawdownTimes::default()),
+    },
+    Entry {
+        name: "sharpe",
+        params: &[],
+        columns: &[("", "sharpe_ratio", Unit::Score)],
+        validate: none,
+        build: |_, risk_free_rate| {
+            single(StreamingSharpeRatio::new(
+                risk_free_rate,
+                TRADING_DAYS_PER_YEAR,
+            ))
+        },
+    },
+    Entry {
+        name: "sortino",
+        params: &[],
+        columns: &[("", "sortino_ratio", Unit::Score)],
+        validate: none,
+        build: |_, risk_free_rate| {
+            single(StreamingSortinoRatio::new(
+                risk_free_rate,
+                TRADING_DAYS_PER_YEAR,
+            ))
+        },
+    },
+    Entry {
+        name: "atr",
+        params: &[("period", 14.0)],
+        columns: &[("", "atr_14", Unit::Price)],
+        validate: |p| windows(p, 1),
+        build: |p, _| single(StreamingATR::new(p[0] as usize)),
+    },
+    Entry {
+        name: "stoch",
+        params: &[("k", 14.0), ("smoothing", 3.0), ("d", 3.0)],
+        columns: &[
+            ("k", "stochastic_k", Unit::Score),
+            ("d", "stochastic_d", Unit::Score),
+        ],
+        validate: |p| windows(p, 1),
+        build: |p, _| {
+            Box::new(Columns {
+                signal: StreamingStochastic::new(p[0] as usize, p[1] as usize, p[2] as usize),
+                values: |s| vec![s.map(|s| s.k), s.map(|s| s.d)],
+            })
+        },
+    },
+    Entry {
+        name: "willr",
+        params: &[("period", 14.0)],
+        columns: &[("", "williams_r_14", Unit::Score)],
+        validate: |p| windows(p, 1),
+        build: |p, _| single(StreamingWilliamsR::new(p[0] as usize)),
+    },
+    Entry {
+        name: "obv",
+        params: &[],
+        columns: &[("", "obv", Unit::Volume)],
+        validate: none,
+        build: |_, _| single(StreamingOBV::default()),
+    },
+    Entry {
+        name: "vwap",
+        params: &[],
+        columns: &[("", "vwap", Unit::Price)],
+        validate: none,
+        build: |_, _| single(StreamingVWAP::default()),
+    },
+    Entry {
+        name: "volume_sma",
+        params: &[("window", 20.0)],
+        columns: &[("", "volume_sma_20", Unit::Volume)],
+        validate: |p| windows(p, 1),
+        build: |p, _| single(StreamingVolumeSMA::new(p[0] as usize)),
+    },
+    Entry {
+        name: "rvol",
+        params: &[("window", 20.0)],
+        columns: &[("", "relative_volume_20", Unit::Score)],
+        validate: |p| windows(p, 1),
+        build: |p, _| single(StreamingRelativeVolume::new(p[0] as usize)),
+    },
+];
+
+///
+/// Names of the indicators that can be configured
+///
+pub fn names() -> Vec<&'static str> {
+    REGISTRY.iter().map(|e| e.name).collect()
+}
+
+///
+/// An indicator with its parameters, parsed from `name` or `name:param,param,...`.
+/// Parameters that are left out take their defaults.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndicatorSpec {
+    name: &'static str,
+    params: Vec<f64>,
+}
+
+impl IndicatorSpec {
+    fn entry(&self) -> &'static Entry {
+        REGISTRY.iter().find(|e| e.name == self.name).unwrap()
+    }
+
+    ///
+    /// Columns this indicator adds to `Indicators`
+    ///
+    pub fn columns(&self) -> Vec<Column> {
+        let prefix = self.params.iter().fold(self.name.to_string(), |prefix, p| {
+            format!("{}_{}", prefix, p)
+        });
+        let entry = self.entry();
+        let defaults = entry.params.iter().map(|(_, default)| *default);
+        let default_params = self.params.iter().copied().eq(defaults);
+        entry
+            .columns
+            .iter()
+            .map(|(suffix, default_name, unit)| Column {
+                name: if default_params {
+                    default_name.to_string()
+                } else if suffix.is_empty() {
+                    prefix.clone()
+                } else {
+                    format!("{}_{}", prefix, suffix)
+                },
+                unit: *unit,
+            })
+            .collect()
+    }
+
+    fn build(&self, risk_free_rate: f64) -> Box<dyn Indicator> {
+        (self.entry().build)(&self.params, risk_free_rate)
+    }
+}
+
+impl FromStr for IndicatorSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, raw) = match s.split_once(':') {
+            Some((name, raw)) => (name.trim(), Some(raw)),
+            None => (s.trim(), None),
+        };
+        let entry = REGISTRY
+            .iter()
+            .find(|e| e.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                format!(
+                    "unknown indicator '{}', expected one of {}",
+                    name,
+                    names().join(", ")
+                )
+            })?;
+
+        let given = match raw {
+            Some(raw) => raw
+                .split(',')
+                .map(|p| {
+                    p.trim()
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|p| p.is_finite())
+                        .ok_or_else(|| format!("invalid parameter '{}' of {}", p, entry.name))
+                })
+                .collect::<Result<Vec<f64>, String>>()?,
+            None => vec![],
+        };
+        if given.len() > entry.params.len() {
+            let expected: Vec<&str> = entry.params.iter().map(|(name, _)| *name).collect();
+            return Err(format!(
+                "{} takes at most {} parameters ({}), got {}",
+                entry.name,
+                entry.params.len(),
+                expected.join(","),
+                given.len()
+            ));
+        }
+        let params: Vec<f64> = entry
+            .params
+            .iter()
+            .enumerate()
+            .map(|(i, (_, default))| given.get(i).copied().unwrap_or(*default))
+            .collect();
+        (entry.validate)(&params).map_err(|e| format!("{}: {}", entry.name, e))?;
+
+        Ok(Self {
+            name: entry.name,
+            params,
+        })
+    }
+}
+
+impl fmt::Display for IndicatorSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        let params: Vec<String> = self.params.iter().map(|p| p.to_string()).collect();
+        if !params.is_empty() {
+            write!(f, ":{}", params.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+///
+/// The indicators the tracker is configured to compute
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndicatorSet {
+    specs: Vec<IndicatorSpec>,
+    risk_free_rate: f64,
+}
+
+impl IndicatorSet {
+    ///
+    /// Set of indicators rating returns against an annual `risk_free_rate`, e.g. 0.02 for 2%.
+    /// Indicators configured more than once are only computed once.
+    ///
+    pub fn new(specs: Vec<IndicatorSpec>, risk_free_rate: f64) -> Self {
+        let mut unique: Vec<IndicatorSpec> = Vec::with_capacity(specs.len());
+        for spec in specs {
+            if !unique.contains(&spec) {
+                unique.push(spec);
+            }
+        }
+        Self {
+            specs: unique,
+            risk_free_rate,
+        }
+    }
+
+    pub fn specs(&self) -> &[IndicatorSpec] {
+        &self.specs
+    }
+
+    ///
+    /// Columns of all indicators, in the order they were configured
+    ///
+    pub fn columns(&self) -> Vec<Column> {
+        self.specs.iter().flat_map(|s| s.columns()).collect()
+    }
+
+    ///
+    /// Fresh signals for a symbol, yielding values in the order of `columns()`
+    ///
+    pub fn build(&self) -> Vec<Box<dyn Indicator>> {
+        self.specs
+            .iter()
+            .map(|s| s.build(self.risk_free_rate))
+            .collect()
+    }
+}
+
+impl Default for IndicatorSet {
+    fn default() -> Self {
+        let specs = DEFAULT_INDICATORS
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        Self::new(specs, 0.0)
+    }
+}
@@ -1,5 +1,7 @@
 use async_trait::async_trait;
 
+use crate::messages::Bar;
+
 ///
 /// A trait to provide a common interface for all signal calculations.
 ///
@@ -20,6 +22,27 @@ pub trait AsyncStockSignal {
     async fn calculate(&self, series: &[f64]) -> Option<Self::SignalType>;
 }
 
+///
+/// A trait to provide a common interface for signals that need
+/// the open, high, low, close and volume of each bar, not just one price.
+///
+#[async_trait]
+pub trait AsyncBarSignal {
+    ///
+    /// The signal's data type.
+    ///
+    type SignalType;
+
+    ///
+    /// Calculate the signal on the provided bars, oldest first.
+    ///
+    /// # Returns
+    ///
+    /// The signal (using the provided type) or `None` on error/invalid data.
+    ///
+    async fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType>;
+}
+
 ///
 /// Struct to implement the stock signal
 /// to calculate the price difference from history
@@ -126,19 +149,1074 @@ impl AsyncStockSignal for WindowedSMA {
     type SignalType = Vec<f64>;
 
     ///
-    /// Find the maximum in a series of f64
+    /// Average every full window of the series, moving a compensated running sum
+    /// along the series instead of adding up each window again
+    ///
+    /// # Returns
+    ///
+    /// One average per window or `None` for an empty series or a window smaller than 2.
     ///
     async fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
         let n = self.window_size;
-        if !series.is_empty() && n > 1 {
-            Some(
-                series
-                    .windows(n)
-                    .map(|w| w.iter().sum::<f64>() / w.len() as f64)
-                    .collect(),
-            )
+        if series.is_empty() || n <= 1 {
+            return None;
+        }
+        if series.len() < n {
+            return Some(vec![]);
+        }
+
+        let mut sum = KahanSum::default();
+        for value in &series[..n] {
+            sum.add(*value);
+        }
+        let mut sma = Vec::with_capacity(series.len() - n + 1);
+        sma.push(sum.value() / n as f64);
+        for (dropped, added) in series.iter().zip(&series[n..]) {
+            sum.add(*added);
+            sum.add(-dropped);
+            sma.push(sum.value() / n as f64);
+        }
+        Some(sma)
+    }
+}
+
+///
+/// Sum that keeps track of the rounding error of every addition
+/// (Neumaier's variant of Kahan summation), so that a running sum
+/// of a long series doesn't drift away from the exact one
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KahanSum {
+    sum: f64,
+    compensation: f64,
+}
+
+impl KahanSum {
+    pub fn add(&mut self, value: f64) {
+        let sum = self.sum + value;
+        // the low-order bits of the smaller operand are the ones lost
+        self.compensation += if self.sum.abs() >= value.abs() {
+            (self.sum - sum) + value
         } else {
-            None
+            (value - sum) + self.sum
+        };
+        self.sum = sum;
+    }
+
+    pub fn value(&self) -> f64 {
+        self.sum + self.compensation
+    }
+}
+
+///
+/// How the first value of an exponential moving average is chosen
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmaSeed {
+    ///
+    /// Start with the first value of the series
+    ///
+    FirstValue,
+    ///
+    /// Start with the simple moving average of the first `n` values,
+    /// the average begins at the `n`th value of the series
+    ///
+    Sma(usize),
+}
+
+///
+/// Struct to implement data and behavior
+/// to calculate Exponential Moving Average
+///
+#[allow(clippy::upper_case_acronyms)]
+pub struct EMA {
+    ///
+    /// Weight of the newest value, between 0 (exclusive) and 1 (inclusive)
+    ///
+    pub alpha: f64,
+
+    ///
+    /// Value the average starts with
+    ///
+    pub seed: EmaSeed,
+}
+
+impl EMA {
+    ///
+    /// EMA over `span` values, i.e. `alpha = 2 / (span + 1)`, seeded with the first value
+    ///
+    pub fn with_span(span: usize) -> Self {
+        Self {
+            alpha: 2.0 / (span as f64 + 1.0),
+            seed: EmaSeed::FirstValue,
+        }
+    }
+
+    pub fn seeded(mut self, seed: EmaSeed) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+#[async_trait]
+impl AsyncStockSignal for EMA {
+    ///
+    /// Signal of Exponential Moving Average
+    ///
+    type SignalType = Vec<f64>;
+
+    ///
+    /// Calculate the EMA for every value of the series from the seed on
+    ///
+    /// # Returns
+    ///
+    /// One average per value, fewer by `n - 1` when seeded with an SMA over `n` values.
+    /// `None` for an invalid `alpha` or a series too short to seed the average.
+    ///
+    async fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        if !(self.alpha > 0.0 && self.alpha <= 1.0) {
+            return None;
+        }
+
+        let (seed, rest) = match self.seed {
+            EmaSeed::FirstValue => (*series.first()?, series.get(1..)?),
+            EmaSeed::Sma(n) if n > 0 && series.len() >= n => {
+                (series[..n].iter().sum::<f64>() / n as f64, &series[n..])
+            }
+            EmaSeed::Sma(_) => return None,
+        };
+
+        let mut ema = Vec::with_capacity(rest.len() + 1);
+        ema.push(seed);
+        for value in rest {
+            let last = ema[ema.len() - 1];
+            ema.push(self.alpha * value + (1.0 - self.alpha) * last);
+        }
+        Some(ema)
+    }
+}
+
+///
+/// RSI of an average gain and loss, 50 if there are neither
+///
+pub(crate) fn relative_strength_index(gain: f64, loss: f64) -> f64 {
+    if loss == 0.0 {
+        if gain == 0.0 {
+            50.0
+        } else {
+            100.0
+        }
+    } else {
+        100.0 - 100.0 / (1.0 + gain / loss)
+    }
+}
+
+///
+/// Struct to implement data and behavior
+/// to calculate the Relative Strength Index with Wilder's smoothing
+///
+#[allow(clippy::upper_case_acronyms)]
+pub struct RSI {
+    ///
+    /// Number of price changes the average gain and loss are smoothed over
+    ///
+    pub period: usize,
+}
+
+#[async_trait]
+impl AsyncStockSignal for RSI {
+    ///
+    /// Signal of Relative Strength Index
+    ///
+    type SignalType = Vec<f64>;
+
+    ///
+    /// Calculate the RSI for every value of the series after the first `period` changes
+    ///
+    /// # Returns
+    ///
+    /// One index between 0 and 100 per value from the `period + 1`th on, 50 while the
+    /// price doesn't move. `None` for a `period` of 0 or a series of `period` values or less.
+    ///
+    async fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        if self.period == 0 || series.len() <= self.period {
+            return None;
+        }
+
+        let n = self.period as f64;
+        let changes: Vec<f64> = series.windows(2).map(|w| w[1] - w[0]).collect();
+        let (first, rest) = changes.split_at(self.period);
+        let mut avg_gain = first.iter().map(|c| c.max(0.0)).sum::<f64>() / n;
+        let mut avg_loss = first.iter().map(|c| (-c).max(0.0)).sum::<f64>() / n;
+
+        let mut rsi = Vec::with_capacity(rest.len() + 1);
+        rsi.push(relative_strength_index(avg_gain, avg_loss));
+        for change in rest {
+            avg_gain = (avg_gain * (n - 1.0) + change.max(0.0)) / n;
+            avg_loss = (avg_loss * (n - 1.0) + (-change).max(0.0)) / n;
+            rsi.push(relative_strength_index(avg_gain, avg_loss));
+        }
+        Some(rsi)
+    }
+}
+
+///
+/// Values of the MACD at one point of a series
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MacdPoint {
+    ///
+    /// Fast EMA minus slow EMA
+    ///
+    pub macd: f64,
+
+    ///
+    /// EMA of the MACD line
+    ///
+    pub signal: f64,
+
+    ///
+    /// MACD line minus signal line
+    ///
+    pub histogram: f64,
+}
+
+///
+/// Struct to implement data and behavior
+/// to calculate Moving Average Convergence/Divergence
+///
+#[allow(clippy::upper_case_acronyms)]
+pub struct MACD {
+    ///
+    /// Span of the fast EMA
+    ///
+    pub fast: usize,
+
+    ///
+    /// Span of the slow EMA, larger than `fast`
+    ///
+    pub slow: usize,
+
+    ///
+    /// Span of the EMA of the MACD line
+    ///
+    pub signal: usize,
+}
+
+impl Default for MACD {
+    fn default() -> Self {
+        Self {
+            fast: 12,
+            slow: 26,
+            signal: 9,
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncStockSignal for MACD {
+    ///
+    /// Signal of MACD line, signal line and histogram
+    ///
+    type SignalType = Vec<MacdPoint>;
+
+    ///
+    /// Calculate the MACD for every value of the series the signal line is known for.
+    /// Every EMA is seeded with the SMA over its span.
+    ///
+    /// # Returns
+    ///
+    /// One point per value from the `slow + signal - 1`th on.
+    /// `None` if `fast` isn't below `slow`, a span is 0 or the series is too short.
+    ///
+    async fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        if self.fast == 0 || self.fast >= self.slow {
+            return None;
+        }
+
+        let fast = EMA::with_span(self.fast)
+            .seeded(EmaSeed::Sma(self.fast))
+            .calculate(series)
+            .await?;
+        let slow = EMA::with_span(self.slow)
+            .seeded(EmaSeed::Sma(self.slow))
+            .calculate(series)
+            .await?;
+        // the fast EMA starts `slow - fast` values earlier
+        let macd: Vec<f64> = fast[self.slow - self.fast..]
+            .iter()
+            .zip(&slow)
+            .map(|(fast, slow)| fast - slow)
+            .collect();
+        let signal = EMA::with_span(self.signal)
+            .seeded(EmaSeed::Sma(self.signal))
+            .calculate(&macd)
+            .await?;
+
+        Some(
+            macd[self.signal - 1..]
+                .iter()
+                .zip(signal)
+                .map(|(&macd, signal)| MacdPoint {
+                    macd,
+                    signal,
+                    histogram: macd - signal,
+                })
+                .collect(),
+        )
+    }
+}
+
+///
+/// Which standard deviation the width of Bollinger Bands is based on
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Deviation {
+    ///
+    /// Divide by the window size, treating the window as the whole population
+    ///
+    Population,
+    ///
+    /// Divide by the window size minus one, treating the window as a sample
+    ///
+    Sample,
+}
+
+///
+/// Values of Bollinger Bands at one point of a series
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BollingerPoint {
+    pub upper: f64,
+
+    ///
+    /// Simple moving average of the window
+    ///
+    pub middle: f64,
+    pub lower: f64,
+
+    ///
+    /// Position of the price within the bands, 0 at the lower and 1 at the upper band
+    ///
+    pub percent_b: f64,
+
+    ///
+    /// Distance between the bands relative to the middle band
+    ///
+    pub bandwidth: f64,
+}
+
+///
+/// Struct to implement data and behavior
+/// to calculate Bollinger Bands
+///
+#[derive(Debug, Clone)]
+pub struct BollingerBands {
+    ///
+    /// Number of day for calculation of the middle band
+    ///
+    pub window_size: usize,
+
+    ///
+    /// Number of standard deviations between the middle and the outer bands
+    ///
+    pub k: f64,
+    pub deviation: Deviation,
+}
+
+impl Default for BollingerBands {
+    fn default() -> Self {
+        Self {
+            window_size: 20,
+            k: 2.0,
+            deviation: Deviation::Population,
+        }
+    }
+}
+
+impl BollingerBands {
+    ///
+    /// Bands over `window_size` values, `k` standard deviations from the middle band
+    ///
+    pub fn new(window_size: usize, k: f64, deviation: Deviation) -> Self {
+        Self {
+            window_size,
+            k,
+            deviation,
+        }
+    }
+
+    pub(crate) fn is_valid(&self) -> bool {
+        self.window_size >= 2 && self.k >= 0.0 && self.k.is_finite()
+    }
+
+    ///
+    /// Bands of one full window, the last value being the current price
+    ///
+    pub(crate) fn point<'a, I>(&self, window: I) -> BollingerPoint
+    where
+        I: IntoIterator<Item = &'a f64>,
+        I::IntoIter: Clone,
+    {
+        let window = window.into_iter();
+        let n = window.clone().count();
+        let divisor = match self.deviation {
+            Deviation::Population => n as f64,
+            Deviation::Sample => (n - 1) as f64,
+        };
+        let middle = window.clone().sum::<f64>() / n as f64;
+        let variance = window.clone().map(|v| (v - middle).powi(2)).sum::<f64>() / divisor;
+        let width = self.k * variance.sqrt();
+        let (upper, lower) = (middle + width, middle - width);
+        let price = window.last().copied().unwrap_or(middle);
+        BollingerPoint {
+            upper,
+            middle,
+            lower,
+            percent_b: if width > 0.0 {
+                (price - lower) / (upper - lower)
+            } else {
+                0.5
+            },
+            bandwidth: if middle != 0.0 {
+                (upper - lower) / middle
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncStockSignal for BollingerBands {
+    ///
+    /// Signal of bands, %B and bandwidth
+    ///
+    type SignalType = Vec<BollingerPoint>;
+
+    ///
+    /// Calculate the bands for every full window of the series
+    ///
+    /// # Returns
+    ///
+    /// One point per window, %B is 0.5 while the bands coincide and the bandwidth
+    /// is 0 for a middle band of 0. `None` for an empty series, a window smaller
+    /// than 2 or a negative `k`.
+    ///
+    async fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        if series.is_empty() || !self.is_valid() {
+            return None;
+        }
+        Some(
+            series
+                .windows(self.window_size)
+                .map(|w| self.point(w))
+                .collect(),
+        )
+    }
+}
+
+///
+/// Number of daily bars in a year, to annualize daily figures
+///
+pub const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+
+pub(crate) fn all_positive(values: &[f64]) -> bool {
+    values.iter().all(|v| *v > 0.0)
+}
+
+pub(crate) fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+///
+/// Standard deviation of a sample, `values` needs at least 2 elements
+///
+fn sample_std_dev(values: &[f64]) -> f64 {
+    let mean = mean(values);
+    let squares: f64 = values.iter().map(|v| (v - mean).powi(2)).sum();
+    (squares / (values.len() - 1) as f64).sqrt()
+}
+
+///
+/// Struct to implement the stock signal
+/// to calculate the logarithmic returns of a series of prices
+///
+pub struct LogReturns {}
+
+#[async_trait]
+impl AsyncStockSignal for LogReturns {
+    ///
+    /// Signal of log returns between two consecutive prices
+    ///
+    type SignalType = Vec<f64>;
+
+    ///
+    /// Calculate `ln(price / previous price)` for every price but the first
+    ///
+    /// # Returns
+    ///
+    /// One return less than there are prices.
+    /// `None` for fewer than 2 prices or a price that isn't positive.
+    ///
+    async fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        if series.len() < 2 || !all_positive(series) {
+            return None;
+        }
+        Some(series.windows(2).map(|w| (w[1] / w[0]).ln()).collect())
+    }
+}
+
+///
+/// Struct to implement the stock signal
+/// to calculate the annualized realized volatility
+///
+pub struct RealizedVolatility {
+    ///
+    /// Number of prices in a year, e.g. `TRADING_DAYS_PER_YEAR` for daily prices
+    ///
+    pub periods_per_year: f64,
+}
+
+#[async_trait]
+impl AsyncStockSignal for RealizedVolatility {
+    ///
+    /// Volatility as a fraction, e.g. 0.2 for 20%
+    ///
+    type SignalType = f64;
+
+    ///
+    /// Calculate the sample standard deviation of the log returns,
+    /// scaled by the square root of `periods_per_year`
+    ///
+    /// # Returns
+    ///
+    /// The volatility or `None` for fewer than 3 prices or a price that isn't positive.
+    ///
+    async fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let returns = LogReturns {}.calculate(series).await?;
+        if returns.len() < 2 {
+            return None;
+        }
+        Some(sample_std_dev(&returns) * self.periods_per_year.sqrt())
+    }
+}
+
+///
+/// Largest fall of a series from a peak to a later trough
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Drawdown {
+    ///
+    /// Fall relative to the peak, e.g. 0.25 for 25%
+    ///
+    pub depth: f64,
+
+    ///
+    /// Index of the peak in the series
+    ///
+    pub peak: usize,
+
+    ///
+    /// Index of the trough in the series
+    ///
+    pub trough: usize,
+}
+
+///
+/// Struct to implement the stock signal
+/// to calculate the maximum drawdown
+///
+pub struct MaxDrawdown {}
+
+#[async_trait]
+impl AsyncStockSignal for MaxDrawdown {
+    ///
+    /// Signal of the deepest drawdown
+    ///
+    type SignalType = Drawdown;
+
+    ///
+    /// Find the largest relative fall from a price to any later price
+    ///
+    /// # Returns
+    ///
+    /// The deepest drawdown, of depth 0 at the first price if prices never fall.
+    /// `None` for an empty series or a price that isn't positive.
+    ///
+    async fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        if series.is_empty() || !all_positive(series) {
+            return None;
+        }
+
+        let mut peak = 0;
+        let mut deepest = Drawdown {
+            depth: 0.0,
+            peak: 0,
+            trough: 0,
+        };
+        for (i, price) in series.iter().enumerate() {
+            if *price > series[peak] {
+                peak = i;
+            }
+            let depth = (series[peak] - price) / series[peak];
+            if depth > deepest.depth {
+                deepest = Drawdown {
+                    depth,
+                    peak,
+                    trough: i,
+                };
+            }
+        }
+        Some(deepest)
+    }
+}
+
+///
+/// Struct to implement the stock signal
+/// to calculate the annualized Sharpe ratio of the log returns
+///
+pub struct SharpeRatio {
+    ///
+    /// Annual return of a risk-free investment, e.g. 0.02 for 2%
+    ///
+    pub risk_free_rate: f64,
+
+    ///
+    /// Number of prices in a year, e.g. `TRADING_DAYS_PER_YEAR` for daily prices
+    ///
+    pub periods_per_year: f64,
+}
+
+#[async_trait]
+impl AsyncStockSignal for SharpeRatio {
+    ///
+    /// Signal of the Sharpe ratio
+    ///
+    type SignalType = f64;
+
+    ///
+    /// Calculate the mean excess return over its standard deviation,
+    /// scaled by the square root of `periods_per_year`
+    ///
+    /// # Returns
+    ///
+    /// The ratio or `None` for fewer than 3 prices, a price that isn't positive
+    /// or returns that don't vary.
+    ///
+    async fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let excess = excess_returns(series, self.risk_free_rate, self.periods_per_year).await?;
+        let std_dev = sample_std_dev(&excess);
+        (std_dev > 0.0).then(|| mean(&excess) / std_dev * self.periods_per_year.sqrt())
+    }
+}
+
+///
+/// Struct to implement the stock signal
+/// to calculate the annualized Sortino ratio of the log returns
+///
+pub struct SortinoRatio {
+    ///
+    /// Annual return of a risk-free investment, e.g. 0.02 for 2%
+    ///
+    pub risk_free_rate: f64,
+
+    ///
+    /// Number of prices in a year, e.g. `TRADING_DAYS_PER_YEAR` for daily prices
+    ///
+    pub periods_per_year: f64,
+}
+
+#[async_trait]
+impl AsyncStockSignal for SortinoRatio {
+    ///
+    /// Signal of the Sortino ratio
+    ///
+    type SignalType = f64;
+
+    ///
+    /// Calculate the mean excess return over the downside deviation, i.e. the root mean
+    /// square of the negative excess returns, scaled by the square root of `periods_per_year`
+    ///
+    /// # Returns
+    ///
+    /// The ratio or `None` for fewer than 3 prices, a price that isn't positive
+    /// or no return below the risk-free rate.
+    ///
+    async fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let excess = excess_returns(series, self.risk_free_rate, self.periods_per_year).await?;
+        let downside =
+            (excess.iter().map(|r| r.min(0.0).powi(2)).sum::<f64>() / excess.len() as f64).sqrt();
+        (downside > 0.0).then(|| mean(&excess) / downside * self.periods_per_year.sqrt())
+    }
+}
+
+///
+/// Log returns of a series minus the risk-free return of one period, at least 2 of them
+///
+async fn excess_returns(
+    series: &[f64],
+    risk_free_rate: f64,
+    periods_per_year: f64,
+) -> Option<Vec<f64>> {
+    let returns = LogReturns {}.calculate(series).await?;
+    if returns.len() < 2 || !all_positive(&[periods_per_year]) {
+        return None;
+    }
+    let per_period = risk_free_rate / periods_per_year;
+    Some(returns.iter().map(|r| r - per_period).collect())
+}
+
+///
+/// Highest high and lowest low of a window of bars
+///
+pub(crate) fn range<'a>(bars: impl IntoIterator<Item = &'a Bar>) -> (f64, f64) {
+    bars.into_iter()
+        .fold((f64::MIN, f64::MAX), |(high, low), bar| {
+            (high.max(bar.high), low.min(bar.low))
+        })
+}
+
+///
+/// Struct to implement data and behavior
+/// to calculate the Average True Range with Wilder's smoothing
+///
+#[allow(clippy::upper_case_acronyms)]
+pub struct ATR {
+    ///
+    /// Number of true ranges the average is smoothed over
+    ///
+    pub period: usize,
+}
+
+#[async_trait]
+impl AsyncBarSignal for ATR {
+    ///
+    /// Signal of Average True Range
+    ///
+    type SignalType = Vec<f64>;
+
+    ///
+    /// Calculate the ATR for every bar from the `period`th on. The true range of a bar
+    /// is its high minus its low, widened to the previous close if that is outside.
+    ///
+    /// # Returns
+    ///
+    /// One average per bar from the `period`th on.
+    /// `None` for a `period` of 0 or fewer bars than `period`.
+    ///
+    async fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        if self.period == 0 || bars.len() < self.period {
+            return None;
+        }
+
+        let n = self.period as f64;
+        let true_ranges: Vec<f64> = bars
+            .iter()
+            .enumerate()
+            .map(|(i, bar)| match i.checked_sub(1).map(|i| bars[i].close) {
+                Some(close) => bar.high.max(close) - bar.low.min(close),
+                None => bar.high - bar.low,
+            })
+            .collect();
+        let (first, rest) = true_ranges.split_at(self.period);
+
+        let mut atr = Vec::with_capacity(rest.len() + 1);
+        atr.push(mean(first));
+        for true_range in rest {
+            let last = atr[atr.len() - 1];
+            atr.push((last * (n - 1.0) + true_range) / n);
+        }
+        Some(atr)
+    }
+}
+
+///
+/// Values of the Stochastic Oscillator at one bar
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StochasticPoint {
+    ///
+    /// Close within the range of the last bars, between 0 and 100
+    ///
+    pub k: f64,
+
+    ///
+    /// Simple moving average of %K
+    ///
+    pub d: f64,
+}
+
+///
+/// Struct to implement data and behavior
+/// to calculate the Stochastic Oscillator
+///
+pub struct Stochastic {
+    ///
+    /// Number of bars the high and low of the range are taken from
+    ///
+    pub k_period: usize,
+
+    ///
+    /// Number of raw %K values averaged into %K, 1 for the fast oscillator
+    ///
+    pub k_smoothing: usize,
+
+    ///
+    /// Number of %K values averaged into %D
+    ///
+    pub d_period: usize,
+}
+
+impl Default for Stochastic {
+    fn default() -> Self {
+        Self {
+            k_period: 14,
+            k_smoothing: 3,
+            d_period: 3,
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncBarSignal for Stochastic {
+    ///
+    /// Signal of %K and %D
+    ///
+    type SignalType = Vec<StochasticPoint>;
+
+    ///
+    /// Calculate %K and %D for every bar %D is known for.
+    /// The raw %K is 50 while the range is flat.
+    ///
+    /// # Returns
+    ///
+    /// One point per bar from the `k_period + k_smoothing + d_period - 2`th on.
+    /// `None` for a period of 0 or too few bars.
+    ///
+    async fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        let (k_period, smoothing, d_period) = (self.k_period, self.k_smoothing, self.d_period);
+        if k_period == 0 || smoothing == 0 || d_period == 0 {
+            return None;
+        }
+        if bars.len() + 2 < k_period + smoothing + d_period {
+            return None;
+        }
+
+        let raw: Vec<f64> = bars
+            .windows(k_period)
+            .map(|w| {
+                let (high, low) = range(w);
+                if high > low {
+                    100.0 * (w[k_period - 1].close - low) / (high - low)
+                } else {
+                    50.0
+                }
+            })
+            .collect();
+        let k: Vec<f64> = raw.windows(smoothing).map(mean).collect();
+        let d: Vec<f64> = k.windows(d_period).map(mean).collect();
+
+        Some(
+            k[d_period - 1..]
+                .iter()
+                .zip(d)
+                .map(|(&k, d)| StochasticPoint { k, d })
+                .collect(),
+        )
+    }
+}
+
+///
+/// Struct to implement data and behavior
+/// to calculate Williams %R
+///
+pub struct WilliamsR {
+    ///
+    /// Number of bars the high and low of the range are taken from
+    ///
+    pub period: usize,
+}
+
+#[async_trait]
+impl AsyncBarSignal for WilliamsR {
+    ///
+    /// Signal of Williams %R
+    ///
+    type SignalType = Vec<f64>;
+
+    ///
+    /// Calculate how far the close is below the high of the last `period` bars,
+    /// relative to their range. The result is -50 while the range is flat.
+    ///
+    /// # Returns
+    ///
+    /// One value between -100 and 0 per bar from the `period`th on.
+    /// `None` for a `period` of 0 or fewer bars than `period`.
+    ///
+    async fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        if self.period == 0 || bars.len() < self.period {
+            return None;
+        }
+
+        Some(
+            bars.windows(self.period)
+                .map(|w| {
+                    let (high, low) = range(w);
+                    if high > low {
+                        -100.0 * (high - w[self.period - 1].close) / (high - low)
+                    } else {
+                        -50.0
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+///
+/// Struct to implement data and behavior
+/// to calculate On-Balance Volume
+///
+#[allow(clippy::upper_case_acronyms)]
+pub struct OBV {}
+
+#[async_trait]
+impl AsyncBarSignal for OBV {
+    ///
+    /// Signal of On-Balance Volume
+    ///
+    type SignalType = Vec<f64>;
+
+    ///
+    /// Add up the volume of bars closing higher than the previous one and subtract
+    /// the volume of bars closing lower, starting at 0
+    ///
+    /// # Returns
+    ///
+    /// One running total per bar or `None` for no bars.
+    ///
+    async fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        if bars.is_empty() {
+            return None;
+        }
+
+        let mut obv = Vec::with_capacity(bars.len());
+        obv.push(0.0);
+        for w in bars.windows(2) {
+            let last = obv[obv.len() - 1];
+            let volume = w[1].volume as f64;
+            obv.push(if w[1].close > w[0].close {
+                last + volume
+            } else if w[1].close < w[0].close {
+                last - volume
+            } else {
+                last
+            });
+        }
+        Some(obv)
+    }
+}
+
+///
+/// Struct to implement data and behavior
+/// to calculate the Volume Weighted Average Price
+///
+#[allow(clippy::upper_case_acronyms)]
+pub struct VWAP {}
+
+#[async_trait]
+impl AsyncBarSignal for VWAP {
+    ///
+    /// Average price over all bars
+    ///
+    type SignalType = f64;
+
+    ///
+    /// Average the typical price `(high + low + close) / 3` of the bars weighted by their volume
+    ///
+    /// # Returns
+    ///
+    /// The VWAP or `None` if nothing was traded.
+    ///
+    async fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        let (turnover, volume) = bars.iter().fold((0.0, 0.0), |(turnover, volume), bar| {
+            let typical = (bar.high + bar.low + bar.close) / 3.0;
+            (
+                turnover + typical * bar.volume as f64,
+                volume + bar.volume as f64,
+            )
+        });
+        (volume > 0.0).then_some(turnover / volume)
+    }
+}
+
+///
+/// Struct to implement data and behavior
+/// to calculate the Simple Moving Average of the traded volume
+///
+pub struct VolumeSMA {
+    ///
+    /// Number of bars for calculation of the average
+    ///
+    pub window_size: usize,
+}
+
+#[async_trait]
+impl AsyncBarSignal for VolumeSMA {
+    ///
+    /// Signal of the average volume
+    ///
+    type SignalType = Vec<f64>;
+
+    ///
+    /// Average the volume over every full window of bars
+    ///
+    /// # Returns
+    ///
+    /// One average per window, `None` for no bars or a window of 0.
+    ///
+    async fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        if bars.is_empty() || self.window_size == 0 {
+            return None;
+        }
+        let volumes: Vec<f64> = bars.iter().map(|b| b.volume as f64).collect();
+        Some(volumes.windows(self.window_size).map(mean).collect())
+    }
+}
+
+///
+/// Struct to implement data and behavior
+/// to calculate the volume of the last bar relative to the ones before
+///
+pub struct RelativeVolume {
+    ///
+    /// Number of bars before the last one the volume is averaged over
+    ///
+    pub window_size: usize,
+}
+
+#[async_trait]
+impl AsyncBarSignal for RelativeVolume {
+    ///
+    /// Ratio of volumes, e.g. 2 for twice the usual volume
+    ///
+    type SignalType = f64;
+
+    ///
+    /// Divide the volume of the last bar by the average volume of the `window_size` bars before
+    ///
+    /// # Returns
+    ///
+    /// The ratio or `None` for a window of 0, too few bars or no volume in the window.
+    ///
+    async fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        let n = self.window_size;
+        if n == 0 || bars.len() <= n {
+            return None;
         }
+        let (last, window) = bars[bars.len() - n - 1..].split_last()?;
+        let average = window.iter().map(|b| b.volume as f64).sum::<f64>() / n as f64;
+        (average > 0.0).then(|| last.volume as f64 / average)
     }
 }
@@ -0,0 +1,121 @@
+use async_std::fs;
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use crate::messages::*;
+
+///
+/// Magic string at the beginning of every snapshot file
+///
+const SNAPSHOT_MAGIC: &str = "stock-tracker-snapshot";
+
+///
+/// Version of the snapshot format written by this build.
+/// Bump it whenever the layout of `Snapshot` changes.
+///
+pub const SNAPSHOT_VERSION: u32 = 3;
+
+///
+/// Oldest snapshot format this build still reads. Version 2 stored the default
+/// indicators as fields, which have the names and values of the columns now.
+///
+const OLDEST_SNAPSHOT_VERSION: u32 = 2;
+
+///
+/// State of the `DataHolder` persisted to disk
+///
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Snapshot {
+    pub saved_at: DateTime<Utc>,
+    pub indicators: Vec<Indicators>,
+}
+
+///
+/// Serialize a snapshot into the on-disk format:
+/// a header line `<magic> <version> <crc32>` followed by the JSON payload.
+///
+pub fn encode(snapshot: &Snapshot) -> Result<Vec<u8>> {
+    let payload = serde_json::to_vec(snapshot)?;
+    let header = format!(
+        "{} {} {:08x}\n",
+        SNAPSHOT_MAGIC,
+        SNAPSHOT_VERSION,
+        crc32fast::hash(&payload)
+    );
+
+    let mut bytes = header.into_bytes();
+    bytes.extend_from_slice(&payload);
+    Ok(bytes)
+}
+
+///
+/// Parse a snapshot from the on-disk format.
+///
+/// # Returns
+///
+/// The snapshot or an `InvalidData` error if the header is malformed,
+/// the version is unknown or the checksum does not match the payload.
+///
+pub fn decode(bytes: &[u8]) -> Result<Snapshot> {
+    let invalid = |reason: String| Error::new(ErrorKind::InvalidData, reason);
+
+    let newline = bytes
+        .iter()
+        .position(|b| *b == b'\n')
+        .ok_or_else(|| invalid("missing snapshot header".to_string()))?;
+    let (header, payload) = (&bytes[..newline], &bytes[newline + 1..]);
+    let header = std::str::from_utf8(header)
+        .map_err(|_| invalid("snapshot header is not valid UTF-8".to_string()))?;
+
+    let fields: Vec<&str> = header.split(' ').collect();
+    if fields.len() != 3 || fields[0] != SNAPSHOT_MAGIC {
+        return Err(invalid(format!("malformed snapshot header '{}'", header)));
+    }
+
+    let version: u32 = fields[1]
+        .parse()
+        .map_err(|_| invalid(format!("malformed snapshot version '{}'", fields[1])))?;
+    if !(OLDEST_SNAPSHOT_VERSION..=SNAPSHOT_VERSION).contains(&version) {
+        return Err(invalid(format!(
+            "unsupported snapshot version {} (expected {} to {})",
+            version, OLDEST_SNAPSHOT_VERSION, SNAPSHOT_VERSION
+        )));
+    }
+
+    let checksum = u32::from_str_radix(fields[2], 16)
+        .map_err(|_| invalid(format!("malformed snapshot checksum '{}'", fields[2])))?;
+    if checksum != crc32fast::hash(payload) {
+        return Err(invalid("snapshot checksum mismatch".to_string()));
+    }
+
+    Ok(serde_json::from_slice(payload)?)
+}
+
+///
+/// Read a snapshot from `path`.
+///
+/// # Returns
+///
+/// `None` if the file does not exist, an error if it exists but cannot be decoded.
+///
+pub async fn load(path: &Path) -> Result<Option<Snapshot>> {
+    match fs::read(path).await {
+        Ok(bytes) => decode(&bytes).map(Some),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+///
+/// Write a snapshot to `path`.
+/// The file is written next to the target first and renamed over it,
+/// so a crash while saving never leaves a truncated snapshot behind.
+///
+pub async fn save(path: &Path, snapshot: &Snapshot) -> Result<()> {
+    let bytes = encode(snapshot)?;
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, bytes).await?;
+    fs::rename(&tmp_path, path).await
+}
@@ -0,0 +1,982 @@
+use std::collections::VecDeque;
+use std::iter;
+
+use crate::messages::Bar;
+use crate::signals::*;
+
+///
+/// A trait to provide a common interface for signals that are updated one bar
+/// at a time instead of being calculated over the whole series again, the
+/// incremental counterpart of `AsyncStockSignal` and `AsyncBarSignal`.
+/// Signals of a single price use the adjusted close of the bars.
+///
+pub trait StreamingSignal {
+    ///
+    /// The signal's data type.
+    ///
+    type Output;
+
+    ///
+    /// Add the next bar of the series.
+    ///
+    /// # Returns
+    ///
+    /// The signal up to and including the bar, `None` while there are too few bars
+    /// or once the data turned out to be invalid.
+    ///
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output>;
+
+    ///
+    /// The signal as if the bar was added, without adding it,
+    /// e.g. for a bar that may still change.
+    ///
+    fn peek(&self, bar: &Bar) -> Option<Self::Output>;
+}
+
+///
+/// Last `size` values of a series
+///
+#[derive(Debug, Clone)]
+struct Window<T> {
+    size: usize,
+    values: VecDeque<T>,
+}
+
+impl<T> Window<T> {
+    fn new(size: usize) -> Self {
+        Self {
+            size,
+            values: VecDeque::new(),
+        }
+    }
+
+    ///
+    /// Add a value, dropping the oldest one if the window was full
+    ///
+    /// # Returns
+    ///
+    /// The dropped value
+    ///
+    fn push(&mut self, value: T) -> Option<T> {
+        self.values.push_back(value);
+        if self.values.len() > self.size {
+            self.values.pop_front()
+        } else {
+            None
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.size > 0 && self.values.len() == self.size
+    }
+
+    ///
+    /// Whether the window would be full with another value
+    ///
+    fn fills(&self) -> bool {
+        self.size > 0 && self.values.len() + 1 >= self.size
+    }
+
+    ///
+    /// The values as if `value` was pushed, without pushing it
+    ///
+    fn peek<'a>(&'a self, value: &'a T) -> impl Iterator<Item = &'a T> + Clone {
+        let dropped = (self.values.len() + 1).saturating_sub(self.size);
+        self.values.iter().skip(dropped).chain(iter::once(value))
+    }
+}
+
+///
+/// Incremental counterpart of `PriceDifference`
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StreamingPriceDifference {
+    first: Option<f64>,
+}
+
+impl StreamingSignal for StreamingPriceDifference {
+    ///
+    /// Absolute and relative difference to the first price
+    ///
+    type Output = (f64, f64);
+
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output> {
+        let first = *self.first.get_or_insert(bar.adjclose);
+        let abs_diff = bar.adjclose - first;
+        let first = if first == 0.0 { 1.0 } else { first };
+        Some((abs_diff, abs_diff / first))
+    }
+
+    fn peek(&self, bar: &Bar) -> Option<Self::Output> {
+        let mut next = *self;
+        next.update(bar)
+    }
+}
+
+///
+/// Incremental counterpart of `MinPrice`
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StreamingMinPrice {
+    min: Option<f64>,
+}
+
+impl StreamingSignal for StreamingMinPrice {
+    type Output = f64;
+
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output> {
+        let min = self.min.unwrap_or(f64::MAX).min(bar.adjclose);
+        self.min = Some(min);
+        self.min
+    }
+
+    fn peek(&self, bar: &Bar) -> Option<Self::Output> {
+        let mut next = *self;
+        next.update(bar)
+    }
+}
+
+///
+/// Incremental counterpart of `MaxPrice`
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StreamingMaxPrice {
+    max: Option<f64>,
+}
+
+impl StreamingSignal for StreamingMaxPrice {
+    type Output = f64;
+
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output> {
+        let max = self.max.unwrap_or(f64::MIN).max(bar.adjclose);
+        self.max = Some(max);
+        self.max
+    }
+
+    fn peek(&self, bar: &Bar) -> Option<Self::Output> {
+        let mut next = *self;
+        next.update(bar)
+    }
+}
+
+///
+/// Incremental counterpart of `WindowedSMA`, keeping a running sum of the window
+///
+#[derive(Debug, Clone)]
+pub struct StreamingSMA {
+    window: Window<f64>,
+    sum: KahanSum,
+}
+
+impl StreamingSMA {
+    pub fn new(window_size: usize) -> Self {
+        Self {
+            window: Window::new(window_size),
+            sum: KahanSum::default(),
+        }
+    }
+
+    fn push(&mut self, value: f64) -> Option<f64> {
+        let average = self.peek_value(value);
+        self.sum.add(value);
+        if let Some(dropped) = self.window.push(value) {
+            self.sum.add(-dropped);
+        }
+        average
+    }
+
+    ///
+    /// The average as if `value` was pushed, without pushing it
+    ///
+    fn peek_value(&self, value: f64) -> Option<f64> {
+        if !self.window.fills() {
+            return None;
+        }
+        let mut sum = self.sum;
+        sum.add(value);
+        if self.window.is_full() {
+            sum.add(-self.window.values[0]);
+        }
+        Some(sum.value() / self.window.size as f64)
+    }
+}
+
+impl StreamingSignal for StreamingSMA {
+    ///
+    /// Average of the last `window_size` prices, which has to be at least 2
+    ///
+    type Output = f64;
+
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output> {
+        if self.window.size < 2 {
+            return None;
+        }
+        self.push(bar.adjclose)
+    }
+
+    fn peek(&self, bar: &Bar) -> Option<Self::Output> {
+        if self.window.size < 2 {
+            return None;
+        }
+        self.peek_value(bar.adjclose)
+    }
+}
+
+///
+/// Incremental counterpart of `EMA`
+///
+#[derive(Debug, Clone, Copy)]
+pub struct StreamingEMA {
+    alpha: f64,
+    seed: EmaSeed,
+    seed_sum: f64,
+    seen: usize,
+    value: Option<f64>,
+}
+
+impl StreamingEMA {
+    ///
+    /// EMA over `span` values, i.e. `alpha = 2 / (span + 1)`, seeded with the first value
+    ///
+    pub fn with_span(span: usize) -> Self {
+        Self::new(2.0 / (span as f64 + 1.0), EmaSeed::FirstValue)
+    }
+
+    pub fn new(alpha: f64, seed: EmaSeed) -> Self {
+        Self {
+            alpha,
+            seed,
+            seed_sum: 0.0,
+            seen: 0,
+            value: None,
+        }
+    }
+
+    pub fn seeded(self, seed: EmaSeed) -> Self {
+        Self::new(self.alpha, seed)
+    }
+
+    fn push(&mut self, value: f64) -> Option<f64> {
+        if self.alpha.is_nan() || self.alpha <= 0.0 || self.alpha > 1.0 {
+            return None;
+        }
+
+        self.value = match (self.value, self.seed) {
+            (Some(last), _) => Some(self.alpha * value + (1.0 - self.alpha) * last),
+            (None, EmaSeed::FirstValue) => Some(value),
+            (None, EmaSeed::Sma(n)) => {
+                self.seed_sum += value;
+                self.seen += 1;
+                (self.seen == n).then(|| self.seed_sum / n as f64)
+            }
+        };
+        self.value
+    }
+}
+
+impl StreamingSignal for StreamingEMA {
+    type Output = f64;
+
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output> {
+        self.push(bar.adjclose)
+    }
+
+    fn peek(&self, bar: &Bar) -> Option<Self::Output> {
+        let mut next = *self;
+        next.update(bar)
+    }
+}
+
+///
+/// Incremental counterpart of `RSI`
+///
+#[derive(Debug, Clone, Copy)]
+pub struct StreamingRSI {
+    period: usize,
+    last_price: Option<f64>,
+    changes: usize,
+    avg_gain: f64,
+    avg_loss: f64,
+}
+
+impl StreamingRSI {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period,
+            last_price: None,
+            changes: 0,
+            avg_gain: 0.0,
+            avg_loss: 0.0,
+        }
+    }
+}
+
+impl StreamingSignal for StreamingRSI {
+    type Output = f64;
+
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output> {
+        let change = bar.adjclose - self.last_price.replace(bar.adjclose)?;
+        if self.period == 0 {
+            return None;
+        }
+
+        let n = self.period as f64;
+        let (gain, loss) = (change.max(0.0), (-change).max(0.0));
+        self.changes += 1;
+        if self.changes <= self.period {
+            // the first average is a plain one, the sums are divided once it's complete
+            self.avg_gain += gain;
+            self.avg_loss += loss;
+            if self.changes < self.period {
+                return None;
+            }
+            self.avg_gain /= n;
+            self.avg_loss /= n;
+        } else {
+            self.avg_gain = (self.avg_gain * (n - 1.0) + gain) / n;
+            self.avg_loss = (self.avg_loss * (n - 1.0) + loss) / n;
+        }
+        Some(relative_strength_index(self.avg_gain, self.avg_loss))
+    }
+
+    fn peek(&self, bar: &Bar) -> Option<Self::Output> {
+        let mut next = *self;
+        next.update(bar)
+    }
+}
+
+///
+/// Incremental counterpart of `MACD`
+///
+#[derive(Debug, Clone, Copy)]
+pub struct StreamingMACD {
+    valid: bool,
+    fast: StreamingEMA,
+    slow: StreamingEMA,
+    signal: StreamingEMA,
+}
+
+impl StreamingMACD {
+    pub fn new(fast: usize, slow: usize, signal: usize) -> Self {
+        let ema = |span| StreamingEMA::with_span(span).seeded(EmaSeed::Sma(span));
+        Self {
+            valid: fast > 0 && fast < slow,
+            fast: ema(fast),
+            slow: ema(slow),
+            signal: ema(signal),
+        }
+    }
+}
+
+impl Default for StreamingMACD {
+    fn default() -> Self {
+        let MACD { fast, slow, signal } = MACD::default();
+        Self::new(fast, slow, signal)
+    }
+}
+
+impl StreamingSignal for StreamingMACD {
+    type Output = MacdPoint;
+
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output> {
+        if !self.valid {
+            return None;
+        }
+
+        let fast = self.fast.push(bar.adjclose);
+        let slow = self.slow.push(bar.adjclose);
+        let macd = fast? - slow?;
+        let signal = self.signal.push(macd)?;
+        Some(MacdPoint {
+            macd,
+            signal,
+            histogram: macd - signal,
+        })
+    }
+
+    fn peek(&self, bar: &Bar) -> Option<Self::Output> {
+        let mut next = *self;
+        next.update(bar)
+    }
+}
+
+///
+/// Incremental counterpart of `BollingerBands`
+///
+#[derive(Debug, Clone)]
+pub struct StreamingBollingerBands {
+    bands: BollingerBands,
+    window: Window<f64>,
+}
+
+impl StreamingBollingerBands {
+    pub fn new(bands: BollingerBands) -> Self {
+        Self {
+            window: Window::new(bands.window_size),
+            bands,
+        }
+    }
+}
+
+impl Default for StreamingBollingerBands {
+    fn default() -> Self {
+        Self::new(BollingerBands::default())
+    }
+}
+
+impl StreamingSignal for StreamingBollingerBands {
+    type Output = BollingerPoint;
+
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output> {
+        let point = self.peek(bar);
+        self.window.push(bar.adjclose);
+        point
+    }
+
+    fn peek(&self, bar: &Bar) -> Option<Self::Output> {
+        if !self.bands.is_valid() || !self.window.fills() {
+            return None;
+        }
+        Some(self.bands.point(self.window.peek(&bar.adjclose)))
+    }
+}
+
+///
+/// Running mean and variance of the log returns of a series (Welford's algorithm)
+///
+#[derive(Debug, Clone, Copy, Default)]
+struct ReturnStats {
+    invalid: bool,
+    last_price: Option<f64>,
+    count: usize,
+    mean: f64,
+    m2: f64,
+}
+
+impl ReturnStats {
+    ///
+    /// Add the next price
+    ///
+    /// # Returns
+    ///
+    /// The log return to the previous price, `None` for the first price or
+    /// if a price wasn't positive.
+    ///
+    fn push(&mut self, price: f64) -> Option<f64> {
+        if !all_positive(&[price]) {
+            self.invalid = true;
+        }
+        let last_price = self.last_price.replace(price);
+        if self.invalid {
+            return None;
+        }
+
+        let value = (price / last_price?).ln();
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+        Some(value)
+    }
+
+    fn sample_std_dev(&self) -> Option<f64> {
+        (self.count >= 2).then(|| (self.m2 / (self.count - 1) as f64).sqrt())
+    }
+}
+
+///
+/// Incremental counterpart of `LogReturns`
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StreamingLogReturns {
+    stats: ReturnStats,
+}
+
+impl StreamingSignal for StreamingLogReturns {
+    ///
+    /// Log return to the previous price
+    ///
+    type Output = f64;
+
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output> {
+        self.stats.push(bar.adjclose)
+    }
+
+    fn peek(&self, bar: &Bar) -> Option<Self::Output> {
+        let mut next = *self;
+        next.update(bar)
+    }
+}
+
+///
+/// Incremental counterpart of `RealizedVolatility`
+///
+#[derive(Debug, Clone, Copy)]
+pub struct StreamingRealizedVolatility {
+    periods_per_year: f64,
+    stats: ReturnStats,
+}
+
+impl StreamingRealizedVolatility {
+    pub fn new(periods_per_year: f64) -> Self {
+        Self {
+            periods_per_year,
+            stats: ReturnStats::default(),
+        }
+    }
+}
+
+impl StreamingSignal for StreamingRealizedVolatility {
+    type Output = f64;
+
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output> {
+        self.stats.push(bar.adjclose);
+        if self.stats.invalid {
+            return None;
+        }
+        Some(self.stats.sample_std_dev()? * self.periods_per_year.sqrt())
+    }
+
+    fn peek(&self, bar: &Bar) -> Option<Self::Output> {
+        let mut next = *self;
+        next.update(bar)
+    }
+}
+
+///
+/// Incremental counterpart of `SharpeRatio`
+///
+#[derive(Debug, Clone, Copy)]
+pub struct StreamingSharpeRatio {
+    risk_free_rate: f64,
+    periods_per_year: f64,
+    stats: ReturnStats,
+}
+
+impl StreamingSharpeRatio {
+    pub fn new(risk_free_rate: f64, periods_per_year: f64) -> Self {
+        Self {
+            risk_free_rate,
+            periods_per_year,
+            stats: ReturnStats::default(),
+        }
+    }
+}
+
+impl StreamingSignal for StreamingSharpeRatio {
+    type Output = f64;
+
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output> {
+        self.stats.push(bar.adjclose);
+        if self.stats.invalid || !all_positive(&[self.periods_per_year]) {
+            return None;
+        }
+        // subtracting the risk-free return shifts the mean but leaves the deviation as is
+        let excess = self.stats.mean - self.risk_free_rate / self.periods_per_year;
+        let std_dev = self.stats.sample_std_dev()?;
+        (std_dev > 0.0).then(|| excess / std_dev * self.periods_per_year.sqrt())
+    }
+
+    fn peek(&self, bar: &Bar) -> Option<Self::Output> {
+        let mut next = *self;
+        next.update(bar)
+    }
+}
+
+///
+/// Incremental counterpart of `SortinoRatio`
+///
+#[derive(Debug, Clone, Copy)]
+pub struct StreamingSortinoRatio {
+    risk_free_rate: f64,
+    periods_per_year: f64,
+    stats: ReturnStats,
+    downside_squares: f64,
+}
+
+impl StreamingSortinoRatio {
+    pub fn new(risk_free_rate: f64, periods_per_year: f64) -> Self {
+        Self {
+            risk_free_rate,
+            periods_per_year,
+            stats: ReturnStats::default(),
+            downside_squares: 0.0,
+        }
+    }
+}
+
+impl StreamingSignal for StreamingSortinoRatio {
+    type Output = f64;
+
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output> {
+        let per_period = self.risk_free_rate / self.periods_per_year;
+        if let Some(value) = self.stats.push(bar.adjclose) {
+            self.downside_squares += (value - per_period).min(0.0).powi(2);
+        }
+        if self.stats.invalid || self.stats.count < 2 || !all_positive(&[self.periods_per_year]) {
+            return None;
+        }
+        let downside = (self.downside_squares / self.stats.count as f64).sqrt();
+        (downside > 0.0)
+            .then(|| (self.stats.mean - per_period) / downside * self.periods_per_year.sqrt())
+    }
+
+    fn peek(&self, bar: &Bar) -> Option<Self::Output> {
+        let mut next = *self;
+        next.update(bar)
+    }
+}
+
+///
+/// Incremental counterpart of `MaxDrawdown`, the indices count the bars updated with
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StreamingMaxDrawdown {
+    invalid: bool,
+    index: usize,
+    peak: Option<(usize, f64)>,
+    deepest: Option<Drawdown>,
+}
+
+impl StreamingMaxDrawdown {
+    ///
+    /// Index of the highest price so far, where a deeper drawdown would start
+    ///
+    pub fn peak(&self) -> Option<usize> {
+        self.peak.map(|(index, _)| index)
+    }
+}
+
+impl StreamingSignal for StreamingMaxDrawdown {
+    type Output = Drawdown;
+
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output> {
+        let (index, price) = (self.index, bar.adjclose);
+        self.index += 1;
+        if !all_positive(&[price]) {
+            self.invalid = true;
+        }
+        if self.invalid {
+            return None;
+        }
+
+        let (peak, peak_price) = match self.peak {
+            Some(peak) if price <= peak.1 => peak,
+            _ => (index, price),
+        };
+        self.peak = Some((peak, peak_price));
+        let depth = (peak_price - price) / peak_price;
+        let deepest = self.deepest.get_or_insert(Drawdown {
+            depth: 0.0,
+            peak: 0,
+            trough: 0,
+        });
+        if depth > deepest.depth {
+            *deepest = Drawdown {
+                depth,
+                peak,
+                trough: index,
+            };
+        }
+        self.deepest
+    }
+
+    fn peek(&self, bar: &Bar) -> Option<Self::Output> {
+        let mut next = *self;
+        next.update(bar)
+    }
+}
+
+///
+/// Incremental counterpart of `ATR`
+///
+#[derive(Debug, Clone, Copy)]
+pub struct StreamingATR {
+    period: usize,
+    last_close: Option<f64>,
+    count: usize,
+    sum: f64,
+    value: Option<f64>,
+}
+
+impl StreamingATR {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period,
+            last_close: None,
+            count: 0,
+            sum: 0.0,
+            value: None,
+        }
+    }
+}
+
+impl StreamingSignal for StreamingATR {
+    type Output = f64;
+
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output> {
+        let true_range = match self.last_close.replace(bar.close) {
+            Some(close) => bar.high.max(close) - bar.low.min(close),
+            None => bar.high - bar.low,
+        };
+        if self.period == 0 {
+            return None;
+        }
+
+        let n = self.period as f64;
+        self.value = match self.value {
+            Some(last) => Some((last * (n - 1.0) + true_range) / n),
+            None => {
+                self.sum += true_range;
+                self.count += 1;
+                (self.count == self.period).then(|| self.sum / n)
+            }
+        };
+        self.value
+    }
+
+    fn peek(&self, bar: &Bar) -> Option<Self::Output> {
+        let mut next = *self;
+        next.update(bar)
+    }
+}
+
+///
+/// Incremental counterpart of `Stochastic`
+///
+#[derive(Debug, Clone)]
+pub struct StreamingStochastic {
+    bars: Window<Bar>,
+    raw: Window<f64>,
+    k: Window<f64>,
+}
+
+impl StreamingStochastic {
+    pub fn new(k_period: usize, k_smoothing: usize, d_period: usize) -> Self {
+        Self {
+            bars: Window::new(k_period),
+            raw: Window::new(k_smoothing),
+            k: Window::new(d_period),
+        }
+    }
+
+    ///
+    /// The raw and smoothed %K as if the bar was added,
+    /// `None` while the windows before them are filling up
+    ///
+    fn peek_k(&self, bar: &Bar) -> (Option<f64>, Option<f64>) {
+        if self.raw.size == 0 || self.k.size == 0 || !self.bars.fills() {
+            return (None, None);
+        }
+        let (high, low) = range(self.bars.peek(bar));
+        let raw = if high > low {
+            100.0 * (bar.close - low) / (high - low)
+        } else {
+            50.0
+        };
+        let k = self
+            .raw
+            .fills()
+            .then(|| self.raw.peek(&raw).sum::<f64>() / self.raw.size as f64);
+        (Some(raw), k)
+    }
+
+    ///
+    /// %K and its average with the ones before
+    ///
+    fn point(&self, k: f64) -> Option<StochasticPoint> {
+        self.k.fills().then(|| StochasticPoint {
+            k,
+            d: self.k.peek(&k).sum::<f64>() / self.k.size as f64,
+        })
+    }
+}
+
+impl Default for StreamingStochastic {
+    fn default() -> Self {
+        let Stochastic {
+            k_period,
+            k_smoothing,
+            d_period,
+        } = Stochastic::default();
+        Self::new(k_period, k_smoothing, d_period)
+    }
+}
+
+impl StreamingSignal for StreamingStochastic {
+    type Output = StochasticPoint;
+
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output> {
+        let (raw, k) = self.peek_k(bar);
+        let point = k.and_then(|k| self.point(k));
+        self.bars.push(bar.clone());
+        if let Some(raw) = raw {
+            self.raw.push(raw);
+        }
+        if let Some(k) = k {
+            self.k.push(k);
+        }
+        point
+    }
+
+    fn peek(&self, bar: &Bar) -> Option<Self::Output> {
+        self.peek_k(bar).1.and_then(|k| self.point(k))
+    }
+}
+
+///
+/// Incremental counterpart of `WilliamsR`
+///
+#[derive(Debug, Clone)]
+pub struct StreamingWilliamsR {
+    bars: Window<Bar>,
+}
+
+impl StreamingWilliamsR {
+    pub fn new(period: usize) -> Self {
+        Self {
+            bars: Window::new(period),
+        }
+    }
+}
+
+impl StreamingSignal for StreamingWilliamsR {
+    type Output = f64;
+
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output> {
+        let value = self.peek(bar);
+        self.bars.push(bar.clone());
+        value
+    }
+
+    fn peek(&self, bar: &Bar) -> Option<Self::Output> {
+        if !self.bars.fills() {
+            return None;
+        }
+        let (high, low) = range(self.bars.peek(bar));
+        Some(if high > low {
+            -100.0 * (high - bar.close) / (high - low)
+        } else {
+            -50.0
+        })
+    }
+}
+
+///
+/// Incremental counterpart of `OBV`
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StreamingOBV {
+    last_close: Option<f64>,
+    total: f64,
+}
+
+impl StreamingSignal for StreamingOBV {
+    type Output = f64;
+
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output> {
+        if let Some(close) = self.last_close.replace(bar.close) {
+            if bar.close > close {
+                self.total += bar.volume as f64;
+            } else if bar.close < close {
+                self.total -= bar.volume as f64;
+            }
+        }
+        Some(self.total)
+    }
+
+    fn peek(&self, bar: &Bar) -> Option<Self::Output> {
+        let mut next = *self;
+        next.update(bar)
+    }
+}
+
+///
+/// Incremental counterpart of `VWAP`
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StreamingVWAP {
+    turnover: f64,
+    volume: f64,
+}
+
+impl StreamingSignal for StreamingVWAP {
+    type Output = f64;
+
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output> {
+        let typical = (bar.high + bar.low + bar.close) / 3.0;
+        self.turnover += typical * bar.volume as f64;
+        self.volume += bar.volume as f64;
+        (self.volume > 0.0).then(|| self.turnover / self.volume)
+    }
+
+    fn peek(&self, bar: &Bar) -> Option<Self::Output> {
+        let mut next = *self;
+        next.update(bar)
+    }
+}
+
+///
+/// Incremental counterpart of `VolumeSMA`
+///
+#[derive(Debug, Clone)]
+pub struct StreamingVolumeSMA {
+    sma: StreamingSMA,
+}
+
+impl StreamingVolumeSMA {
+    pub fn new(window_size: usize) -> Self {
+        Self {
+            sma: StreamingSMA::new(window_size),
+        }
+    }
+}
+
+impl StreamingSignal for StreamingVolumeSMA {
+    type Output = f64;
+
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output> {
+        self.sma.push(bar.volume as f64)
+    }
+
+    fn peek(&self, bar: &Bar) -> Option<Self::Output> {
+        self.sma.peek_value(bar.volume as f64)
+    }
+}
+
+///
+/// Incremental counterpart of `RelativeVolume`
+///
+#[derive(Debug, Clone)]
+pub struct StreamingRelativeVolume {
+    previous: StreamingSMA,
+    average: Option<f64>,
+}
+
+impl StreamingRelativeVolume {
+    pub fn new(window_size: usize) -> Self {
+        Self {
+            previous: StreamingSMA::new(window_size),
+            average: None,
+        }
+    }
+}
+
+impl StreamingSignal for StreamingRelativeVolume {
+    type Output = f64;
+
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output> {
+        let relative = self.peek(bar);
+        self.average = self.previous.push(bar.volume as f64);
+        relative
+    }
+
+    ///
+    /// Relative to the average of the bars before, which the bar doesn't change
+    ///
+    fn peek(&self, bar: &Bar) -> Option<Self::Output> {
+        self.average
+            .filter(|average| *average > 0.0)
+            .map(|average| bar.volume as f64 / average)
+    }
+}
@@ -1,6 +1,21 @@
 #![allow(non_snake_case)]
 
-use crate::signals::*;
+use chrono::prelude::*;
+use manning_lp_async_rust_project_1_m1::signals::*;
+use tide::http::{Method, Request, Response, Url};
+use xactor::*;
+
+use crate::actors::data_holder::{DataHolder, MAX_REPLAY};
+use crate::actors::http_server::{self, AppState};
+use crate::actors::metrics::Metrics;
+use crate::actors::processor::Processor;
+use crate::actors::scheduler::Scheduler;
+use crate::actors::writer;
+use crate::client;
+use crate::messages::*;
+use crate::registry::*;
+use crate::snapshot;
+use crate::streaming::*;
 
 #[async_std::test]
 async fn test_PriceDifference_calculate() {
@@ -72,3 +87,1855 @@ async fn test_WindowedSMA_calculate() {
     let signal = WindowedSMA { window_size: 10 };
     assert_eq!(signal.calculate(&series).await, Some(vec![]));
 }
+
+#[async_std::test]
+async fn test_WindowedSMA_bounds_drift() {
+    // large prices with small moves lose the most bits in a plain running sum
+    let series: Vec<f64> = (0..200_000)
+        .map(|i| 1e8 + (i % 13) as f64 * 0.1 + (i as f64 * 0.001).sin())
+        .collect();
+    let n = 50;
+
+    let sma = WindowedSMA { window_size: n }
+        .calculate(&series)
+        .await
+        .unwrap();
+    assert_eq!(sma.len(), series.len() - n + 1);
+    for (i, average) in sma.iter().enumerate().step_by(9_973) {
+        let exact = series[i..i + n].iter().sum::<f64>() / n as f64;
+        assert!((average - exact).abs() < 1e-6, "{} vs {}", average, exact);
+    }
+}
+
+#[async_std::test]
+async fn test_EMA_calculate() {
+    let series = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+
+    let signal = EMA::with_span(3);
+    assert_eq!(
+        signal.calculate(&series).await,
+        Some(vec![1.0, 1.5, 2.25, 3.125, 4.0625])
+    );
+
+    let signal = EMA::with_span(3).seeded(EmaSeed::Sma(3));
+    assert_eq!(signal.calculate(&series).await, Some(vec![2.0, 3.0, 4.0]));
+
+    // reference values of a 10 day EMA seeded with the 10 day SMA
+    let series = vec![
+        22.27, 22.19, 22.08, 22.17, 22.18, 22.13, 22.23, 22.43, 22.24, 22.29, 22.15, 22.39, 22.38,
+        22.61, 23.36,
+    ];
+    let signal = EMA::with_span(10).seeded(EmaSeed::Sma(10));
+    let ema: Vec<f64> = signal
+        .calculate(&series)
+        .await
+        .unwrap()
+        .iter()
+        .map(|v| (v * 100.0).round() / 100.0)
+        .collect();
+    assert_eq!(ema, vec![22.22, 22.21, 22.24, 22.27, 22.33, 22.52]);
+
+    let signal = EMA {
+        alpha: 1.0,
+        seed: EmaSeed::FirstValue,
+    };
+    assert_eq!(signal.calculate(&[3.0, 7.0]).await, Some(vec![3.0, 7.0]));
+
+    assert_eq!(EMA::with_span(3).calculate(&[]).await, None);
+    let signal = EMA::with_span(3).seeded(EmaSeed::Sma(6));
+    assert_eq!(signal.calculate(&series[..5]).await, None);
+    let signal = EMA::with_span(3).seeded(EmaSeed::Sma(0));
+    assert_eq!(signal.calculate(&series).await, None);
+    for alpha in [0.0, -0.5, 1.5, f64::NAN] {
+        let signal = EMA {
+            alpha,
+            seed: EmaSeed::FirstValue,
+        };
+        assert_eq!(signal.calculate(&series).await, None);
+    }
+}
+
+#[async_std::test]
+async fn test_RSI_calculate() {
+    // reference values of a 14 day RSI with Wilder's smoothing
+    let series = vec![
+        44.34, 44.09, 44.15, 43.61, 44.33, 44.83, 45.10, 45.42, 45.84, 46.08, 45.89, 46.03, 45.61,
+        46.28, 46.28, 46.00, 46.03, 46.41, 46.22, 45.64, 46.21,
+    ];
+    let signal = RSI { period: 14 };
+    let rsi: Vec<f64> = signal
+        .calculate(&series)
+        .await
+        .unwrap()
+        .iter()
+        .map(|v| (v * 100.0).round() / 100.0)
+        .collect();
+    assert_eq!(rsi, vec![70.46, 66.25, 66.48, 69.35, 66.29, 57.92, 62.88]);
+
+    let signal = RSI { period: 3 };
+    assert_eq!(signal.calculate(&[5.0; 6]).await, Some(vec![50.0; 3]));
+    assert_eq!(
+        signal.calculate(&[1.0, 2.0, 3.0, 4.0, 4.0]).await,
+        Some(vec![100.0, 100.0])
+    );
+    assert_eq!(
+        signal.calculate(&[4.0, 3.0, 2.0, 1.0]).await,
+        Some(vec![0.0])
+    );
+
+    assert_eq!(signal.calculate(&[1.0, 2.0, 3.0]).await, None);
+    assert_eq!(signal.calculate(&[]).await, None);
+    assert_eq!(RSI { period: 0 }.calculate(&series).await, None);
+}
+
+#[async_std::test]
+async fn test_MACD_calculate() {
+    let round = |points: Vec<MacdPoint>| -> Vec<(f64, f64, f64)> {
+        let r = |v: f64| (v * 10000.0).round() / 10000.0;
+        points
+            .iter()
+            .map(|p| (r(p.macd), r(p.signal), r(p.histogram)))
+            .collect()
+    };
+
+    // every EMA of a straight line lags it by `(span - 1) / 2`
+    let series: Vec<f64> = (1..=40).map(f64::from).collect();
+    let macd = MACD::default().calculate(&series).await.unwrap();
+    assert_eq!(round(macd), vec![(7.0, 7.0, 0.0); 7]);
+
+    let series = vec![
+        10.0, 11.0, 12.0, 11.0, 13.0, 14.0, 13.0, 15.0, 16.0, 15.0, 17.0, 18.0, 16.0, 19.0, 20.0,
+    ];
+    let signal = MACD {
+        fast: 3,
+        slow: 5,
+        signal: 2,
+    };
+    assert_eq!(
+        round(signal.calculate(&series).await.unwrap())[..3],
+        [
+            (0.7333, 0.6667, 0.0667),
+            (0.4889, 0.5481, -0.0593),
+            (0.6593, 0.6222, 0.037)
+        ]
+    );
+    assert_eq!(
+        round(signal.calculate(&series).await.unwrap()).last(),
+        Some(&(0.8112, 0.7399, 0.0713))
+    );
+
+    assert_eq!(
+        signal.calculate(&series[..6]).await.map(|m| m.len()),
+        Some(1)
+    );
+    assert_eq!(signal.calculate(&series[..5]).await, None);
+    let signal = MACD {
+        fast: 5,
+        slow: 5,
+        signal: 2,
+    };
+    assert_eq!(signal.calculate(&series).await, None);
+    let signal = MACD {
+        fast: 3,
+        slow: 5,
+        signal: 0,
+    };
+    assert_eq!(signal.calculate(&series).await, None);
+}
+
+#[async_std::test]
+async fn test_BollingerBands_calculate() {
+    let round = |points: Vec<BollingerPoint>| -> Vec<[f64; 5]> {
+        let r = |v: f64| (v * 10000.0).round() / 10000.0;
+        points
+            .iter()
+            .map(|p| {
+                [
+                    r(p.upper),
+                    r(p.middle),
+                    r(p.lower),
+                    r(p.percent_b),
+                    r(p.bandwidth),
+                ]
+            })
+            .collect()
+    };
+    let series = vec![2.0, 4.5, 5.3, 6.5, 4.7];
+
+    let signal = BollingerBands {
+        window_size: 3,
+        k: 2.0,
+        deviation: Deviation::Population,
+    };
+    assert_eq!(
+        round(signal.calculate(&series).await.unwrap()),
+        vec![
+            [6.7444, 3.9333, 1.1222, 0.7431, 1.4294],
+            [7.0772, 5.4333, 3.7895, 0.8244, 0.6051],
+            [6.9967, 5.5, 4.0033, 0.2327, 0.5442],
+        ]
+    );
+
+    let signal = BollingerBands::new(3, 2.0, Deviation::Sample);
+    let sample = vec![
+        [7.3762, 3.9333, 0.4905, 0.6985, 1.7506],
+        [7.4466, 5.4333, 3.42, 0.7649, 0.7411],
+        [7.333, 5.5, 3.667, 0.2818, 0.6666],
+    ];
+    assert_eq!(round(signal.calculate(&series).await.unwrap()), sample);
+    let bars = sample_bars(&series, &series, &series);
+    assert_eq!(
+        round(stream(StreamingBollingerBands::new(signal), &bars)),
+        sample
+    );
+
+    let flat = BollingerBands::default()
+        .calculate(&[0.0; 20])
+        .await
+        .unwrap();
+    assert_eq!(round(flat), vec![[0.0, 0.0, 0.0, 0.5, 0.0]]);
+    assert_eq!(
+        BollingerBands::default().calculate(&series).await,
+        Some(vec![])
+    );
+
+    assert_eq!(BollingerBands::default().calculate(&[]).await, None);
+    let signal = BollingerBands {
+        window_size: 1,
+        ..BollingerBands::default()
+    };
+    assert_eq!(signal.calculate(&series).await, None);
+    let signal = BollingerBands {
+        k: -1.0,
+        ..BollingerBands::default()
+    };
+    assert_eq!(signal.calculate(&series).await, None);
+}
+
+#[async_std::test]
+async fn test_risk_signals_calculate() {
+    let r = |v: f64| (v * 1_000_000.0).round() / 1_000_000.0;
+    let series = vec![100.0, 110.0, 99.0, 105.0, 120.0, 90.0, 95.0];
+
+    let returns = LogReturns {}.calculate(&series).await.unwrap();
+    assert_eq!(
+        returns.into_iter().map(r).collect::<Vec<_>>(),
+        vec![0.09531, -0.105361, 0.058841, 0.133531, -0.287682, 0.054067]
+    );
+    assert_eq!(LogReturns {}.calculate(&[100.0]).await, None);
+    assert_eq!(LogReturns {}.calculate(&[100.0, 0.0]).await, None);
+
+    let volatility = RealizedVolatility {
+        periods_per_year: TRADING_DAYS_PER_YEAR,
+    };
+    assert_eq!(volatility.calculate(&series).await.map(r), Some(2.527224));
+    assert_eq!(volatility.calculate(&[5.0; 10]).await, Some(0.0));
+    assert_eq!(volatility.calculate(&[100.0, 101.0]).await, None);
+
+    assert_eq!(
+        MaxDrawdown {}.calculate(&series).await,
+        Some(Drawdown {
+            depth: 0.25,
+            peak: 4,
+            trough: 5
+        })
+    );
+    assert_eq!(
+        MaxDrawdown {}.calculate(&[1.0, 2.0, 3.0]).await,
+        Some(Drawdown {
+            depth: 0.0,
+            peak: 0,
+            trough: 0
+        })
+    );
+    assert_eq!(MaxDrawdown {}.calculate(&[]).await, None);
+
+    let sharpe = |risk_free_rate| SharpeRatio {
+        risk_free_rate,
+        periods_per_year: TRADING_DAYS_PER_YEAR,
+    };
+    assert_eq!(sharpe(0.0).calculate(&series).await.map(r), Some(-0.852445));
+    assert_eq!(
+        sharpe(0.02).calculate(&series).await.map(r),
+        Some(-0.860358)
+    );
+    assert_eq!(sharpe(0.0).calculate(&[5.0; 10]).await, None);
+
+    let sortino = SortinoRatio {
+        risk_free_rate: 0.02,
+        periods_per_year: TRADING_DAYS_PER_YEAR,
+    };
+    assert_eq!(sortino.calculate(&series).await.map(r), Some(-1.094737));
+    let rising = SortinoRatio {
+        risk_free_rate: 0.0,
+        ..sortino
+    };
+    assert_eq!(rising.calculate(&[1.0, 2.0, 3.0]).await, None);
+}
+
+fn sample_bars(high: &[f64], low: &[f64], close: &[f64]) -> Vec<Bar> {
+    high.iter()
+        .zip(low)
+        .zip(close)
+        .enumerate()
+        .map(|(day, ((&high, &low), &close))| Bar {
+            timestamp: Utc.ymd(2022, 3, day as u32 + 1).and_hms(20, 0, 0),
+            open: close,
+            high,
+            low,
+            close,
+            adjclose: close,
+            volume: 1000,
+        })
+        .collect()
+}
+
+#[async_std::test]
+async fn test_bar_signals_calculate() {
+    let r = |v: &f64| (v * 10000.0).round() / 10000.0;
+    let bars = sample_bars(
+        &[12.0, 13.0, 14.0, 13.5, 15.0, 16.0, 15.5, 17.0],
+        &[10.0, 11.0, 12.0, 11.5, 12.5, 14.0, 13.0, 15.0],
+        &[11.0, 12.5, 13.0, 12.0, 14.5, 15.0, 14.0, 16.5],
+    );
+
+    let atr = ATR { period: 3 }.calculate(&bars).await.unwrap();
+    assert_eq!(
+        atr.iter().map(r).collect::<Vec<_>>(),
+        vec![2.0, 2.0, 2.3333, 2.2222, 2.3148, 2.5432]
+    );
+    assert_eq!(ATR { period: 9 }.calculate(&bars).await, None);
+    assert_eq!(ATR { period: 0 }.calculate(&bars).await, None);
+
+    let fast = Stochastic {
+        k_period: 3,
+        k_smoothing: 1,
+        d_period: 1,
+    };
+    let k: Vec<f64> = fast
+        .calculate(&bars)
+        .await
+        .unwrap()
+        .iter()
+        .map(|p| r(&p.k))
+        .collect();
+    assert_eq!(k, vec![75.0, 33.3333, 85.7143, 77.7778, 42.8571, 87.5]);
+
+    let slow = Stochastic {
+        k_period: 3,
+        k_smoothing: 2,
+        d_period: 2,
+    };
+    let points: Vec<(f64, f64)> = slow
+        .calculate(&bars)
+        .await
+        .unwrap()
+        .iter()
+        .map(|p| (r(&p.k), r(&p.d)))
+        .collect();
+    assert_eq!(
+        points,
+        vec![
+            (59.5238, 56.8452),
+            (81.746, 70.6349),
+            (60.3175, 71.0317),
+            (65.1786, 62.748)
+        ]
+    );
+    assert_eq!(slow.calculate(&bars[..5]).await.map(|p| p.len()), Some(1));
+    assert_eq!(slow.calculate(&bars[..4]).await, None);
+
+    let williams = WilliamsR { period: 3 }.calculate(&bars).await.unwrap();
+    assert_eq!(
+        williams.iter().map(r).collect::<Vec<_>>(),
+        vec![-25.0, -66.6667, -14.2857, -22.2222, -57.1429, -12.5]
+    );
+
+    let flat = sample_bars(&[5.0; 20], &[5.0; 20], &[5.0; 20]);
+    assert_eq!(
+        Stochastic::default().calculate(&flat).await.unwrap()[0],
+        StochasticPoint { k: 50.0, d: 50.0 }
+    );
+    assert_eq!(
+        WilliamsR { period: 14 }.calculate(&flat).await.unwrap()[0],
+        -50.0
+    );
+    assert_eq!(WilliamsR { period: 14 }.calculate(&bars).await, None);
+}
+
+#[async_std::test]
+async fn test_volume_signals_calculate() {
+    let mut bars = sample_bars(
+        &[12.0, 13.0, 14.0, 13.5, 15.0, 16.0, 15.5, 17.0],
+        &[10.0, 11.0, 12.0, 11.5, 12.5, 14.0, 13.0, 15.0],
+        &[11.0, 12.5, 13.0, 12.0, 14.5, 15.0, 14.0, 16.5],
+    );
+    for (bar, volume) in bars
+        .iter_mut()
+        .zip([100, 200, 150, 300, 250, 100, 400, 500])
+    {
+        bar.volume = volume;
+    }
+
+    assert_eq!(
+        OBV {}.calculate(&bars).await,
+        Some(vec![0.0, 200.0, 350.0, 50.0, 300.0, 400.0, 0.0, 500.0])
+    );
+    assert_eq!(OBV {}.calculate(&[]).await, None);
+
+    let vwap = VWAP {}.calculate(&bars).await.unwrap();
+    assert_eq!((vwap * 10000.0).round() / 10000.0, 13.9667);
+    assert_eq!(VWAP {}.calculate(&[]).await, None);
+
+    let sma = VolumeSMA { window_size: 3 }.calculate(&bars).await.unwrap();
+    assert_eq!(sma.len(), 6);
+    assert_eq!(sma[0], 150.0);
+    assert_eq!(sma[4], 250.0);
+    assert_eq!(VolumeSMA { window_size: 0 }.calculate(&bars).await, None);
+
+    let relative = RelativeVolume { window_size: 3 };
+    assert_eq!(relative.calculate(&bars).await, Some(2.0));
+    assert_eq!(relative.calculate(&bars[..3]).await, None);
+    for bar in bars.iter_mut() {
+        bar.volume = 0;
+    }
+    assert_eq!(relative.calculate(&bars).await, None);
+    assert_eq!(VWAP {}.calculate(&bars).await, None);
+}
+
+///
+/// A wavy, rising series of daily bars
+///
+fn wavy_bars(days: usize) -> Vec<Bar> {
+    (0..days)
+        .map(|day| {
+            let close = 100.0 + day as f64 * 0.3 + (day as f64 * 0.7).sin() * 5.0;
+            Bar {
+                timestamp: Utc.ymd(2022, 1, 1).and_hms(20, 0, 0)
+                    + chrono::Duration::days(day as i64),
+                open: close - 0.5,
+                high: close + 1.0 + (day % 3) as f64,
+                low: close - 1.0 - (day % 4) as f64,
+                close,
+                adjclose: close,
+                volume: 1000 + (day as u64 * 37) % 500,
+            }
+        })
+        .collect()
+}
+
+///
+/// Feed all bars to a streaming signal and collect its outputs
+///
+fn stream<S: StreamingSignal>(mut signal: S, bars: &[Bar]) -> Vec<S::Output> {
+    bars.iter().filter_map(|bar| signal.update(bar)).collect()
+}
+
+#[async_std::test]
+async fn test_streaming_signals_match_batch() {
+    let close = |v: f64| (v * 1e9).round() / 1e9;
+    let closes = |values: Vec<f64>| values.into_iter().map(close).collect::<Vec<_>>();
+    let bars = wavy_bars(80);
+    let series: Vec<f64> = bars.iter().map(|b| b.adjclose).collect();
+
+    assert_eq!(
+        stream(StreamingPriceDifference::default(), &bars)
+            .last()
+            .copied(),
+        PriceDifference {}.calculate(&series).await
+    );
+    assert_eq!(
+        stream(StreamingMinPrice::default(), &bars).last().copied(),
+        MinPrice {}.calculate(&series).await
+    );
+    assert_eq!(
+        stream(StreamingMaxPrice::default(), &bars).last().copied(),
+        MaxPrice {}.calculate(&series).await
+    );
+    assert_eq!(
+        closes(stream(StreamingSMA::new(30), &bars)),
+        closes(
+            WindowedSMA { window_size: 30 }
+                .calculate(&series)
+                .await
+                .unwrap()
+        )
+    );
+    assert_eq!(
+        stream(StreamingEMA::with_span(10), &bars),
+        EMA::with_span(10).calculate(&series).await.unwrap()
+    );
+    assert_eq!(
+        stream(StreamingEMA::with_span(30).seeded(EmaSeed::Sma(30)), &bars),
+        EMA::with_span(30)
+            .seeded(EmaSeed::Sma(30))
+            .calculate(&series)
+            .await
+            .unwrap()
+    );
+    assert_eq!(
+        stream(StreamingRSI::new(14), &bars),
+        RSI { period: 14 }.calculate(&series).await.unwrap()
+    );
+    assert_eq!(
+        stream(StreamingMACD::default(), &bars),
+        MACD::default().calculate(&series).await.unwrap()
+    );
+    assert_eq!(
+        stream(StreamingBollingerBands::default(), &bars),
+        BollingerBands::default().calculate(&series).await.unwrap()
+    );
+    assert_eq!(
+        closes(stream(StreamingLogReturns::default(), &bars)),
+        closes(LogReturns {}.calculate(&series).await.unwrap())
+    );
+    assert_eq!(
+        stream(
+            StreamingRealizedVolatility::new(TRADING_DAYS_PER_YEAR),
+            &bars
+        )
+        .last()
+        .map(|v| close(*v)),
+        RealizedVolatility {
+            periods_per_year: TRADING_DAYS_PER_YEAR
+        }
+        .calculate(&series)
+        .await
+        .map(close)
+    );
+    assert_eq!(
+        stream(StreamingMaxDrawdown::default(), &bars)
+            .last()
+            .copied(),
+        MaxDrawdown {}.calculate(&series).await
+    );
+    for risk_free_rate in [0.0, 0.05] {
+        assert_eq!(
+            stream(
+                StreamingSharpeRatio::new(risk_free_rate, TRADING_DAYS_PER_YEAR),
+                &bars
+            )
+            .last()
+            .map(|v| close(*v)),
+            SharpeRatio {
+                risk_free_rate,
+                periods_per_year: TRADING_DAYS_PER_YEAR
+            }
+            .calculate(&series)
+            .await
+            .map(close)
+        );
+        assert_eq!(
+            stream(
+                StreamingSortinoRatio::new(risk_free_rate, TRADING_DAYS_PER_YEAR),
+                &bars
+            )
+            .last()
+            .map(|v| close(*v)),
+            SortinoRatio {
+                risk_free_rate,
+                periods_per_year: TRADING_DAYS_PER_YEAR
+            }
+            .calculate(&series)
+            .await
+            .map(close)
+        );
+    }
+    assert_eq!(
+        stream(StreamingATR::new(14), &bars),
+        ATR { period: 14 }.calculate(&bars).await.unwrap()
+    );
+    assert_eq!(
+        stream(StreamingStochastic::default(), &bars),
+        Stochastic::default().calculate(&bars).await.unwrap()
+    );
+    assert_eq!(
+        stream(StreamingWilliamsR::new(14), &bars),
+        WilliamsR { period: 14 }.calculate(&bars).await.unwrap()
+    );
+    assert_eq!(
+        stream(StreamingOBV::default(), &bars),
+        OBV {}.calculate(&bars).await.unwrap()
+    );
+    assert_eq!(
+        stream(StreamingVWAP::default(), &bars).last().copied(),
+        VWAP {}.calculate(&bars).await
+    );
+    assert_eq!(
+        closes(stream(StreamingVolumeSMA::new(20), &bars)),
+        closes(
+            VolumeSMA { window_size: 20 }
+                .calculate(&bars)
+                .await
+                .unwrap()
+        )
+    );
+    assert_eq!(
+        stream(StreamingRelativeVolume::new(20), &bars)
+            .last()
+            .copied(),
+        RelativeVolume { window_size: 20 }.calculate(&bars).await
+    );
+
+    // invalid parameters and data stay invalid
+    assert!(stream(StreamingSMA::new(1), &bars).is_empty());
+    assert!(stream(StreamingRSI::new(0), &bars).is_empty());
+    assert!(stream(StreamingMACD::new(26, 12, 9), &bars).is_empty());
+    let mut broken = bars.clone();
+    broken[40].adjclose = 0.0;
+    assert_eq!(stream(StreamingLogReturns::default(), &broken).len(), 39);
+    assert!(stream(StreamingMaxDrawdown::default(), &broken).len() == 40);
+}
+
+#[test]
+fn test_processor_feeds_new_bars_only() {
+    let bars = wavy_bars(80);
+    let quote = |bars: &[Bar]| Quote {
+        cycle: 1,
+        symbol: "AAPL".to_string(),
+        from: bars.first().map(|b| b.timestamp).unwrap_or_else(Utc::now),
+        bars: bars.to_vec(),
+    };
+    let compare = |i: Indicators| serde_json::to_string(&i).unwrap();
+    let specs = DEFAULT_INDICATORS
+        .iter()
+        .map(|s| s.parse().unwrap())
+        .collect();
+    let indicators = IndicatorSet::new(specs, 0.02);
+
+    let mut processor = Processor::new(indicators.clone());
+    let mut updated = bars[..50].to_vec();
+    updated[49].adjclose += 3.0;
+    updated[49].high += 3.0;
+    for quote in [quote(&bars[..30]), quote(&updated), quote(&bars[..60])] {
+        let expected = Processor::new(indicators.clone()).process(&quote).unwrap();
+        assert_eq!(
+            compare(processor.process(&quote).unwrap()),
+            compare(expected)
+        );
+    }
+
+    // a quote starting elsewhere resets the signals of the symbol
+    let later = quote(&bars[20..70]);
+    assert_eq!(
+        compare(processor.process(&later).unwrap()),
+        compare(Processor::new(indicators.clone()).process(&later).unwrap())
+    );
+
+    assert!(processor.process(&quote(&[])).is_none());
+}
+
+#[test]
+fn test_indicator_peek() {
+    let bars = wavy_bars(80);
+    let specs = DEFAULT_INDICATORS
+        .iter()
+        .map(|s| s.parse().unwrap())
+        .collect();
+    for mut indicator in IndicatorSet::new(specs, 0.02).build() {
+        for bar in &bars {
+            // peeking at a bar that changes before it's complete leaves no trace
+            let mut open = bar.clone();
+            open.adjclose += 2.0;
+            open.close += 2.0;
+            open.high += 2.0;
+            open.volume += 100;
+            indicator.peek(&open);
+
+            let peeked = indicator.peek(bar);
+            assert_eq!(peeked, indicator.update(bar));
+        }
+    }
+}
+
+#[test]
+fn test_indicator_specs() {
+    let names = |spec: &str| -> Vec<String> {
+        let spec: IndicatorSpec = spec.parse().unwrap();
+        spec.columns().into_iter().map(|c| c.name).collect()
+    };
+    assert_eq!(names("rsi:14"), vec!["rsi_14"]);
+    assert_eq!(names("SMA:50"), vec!["sma_50"]);
+    assert_eq!(names("obv"), vec!["obv"]);
+    assert_eq!(
+        names("bb:20,2.5"),
+        vec![
+            "bb_20_2.5_0_upper",
+            "bb_20_2.5_0_middle",
+            "bb_20_2.5_0_lower",
+            "bb_20_2.5_0_percent_b",
+            "bb_20_2.5_0_bandwidth"
+        ]
+    );
+    // left out parameters take their defaults
+    assert_eq!(names("macd"), names("macd:12,26,9"));
+    assert_eq!(names("stoch:5"), vec!["stoch_5_3_3_k", "stoch_5_3_3_d"]);
+    let spec: IndicatorSpec = "bb:20".parse().unwrap();
+    assert_eq!(spec.to_string(), "bb:20,2,0");
+    // the defaults keep the names of the fields before indicators were configurable
+    assert_eq!(names("sma"), vec!["last_sma"]);
+    assert_eq!(names("macd"), vec!["macd", "macd_signal", "macd_histogram"]);
+    assert_eq!(
+        names("drawdown"),
+        vec!["max_drawdown", "drawdown_peak", "drawdown_trough"]
+    );
+    assert_eq!(names("bb:20,2,1")[0], "bb_20_2_1_upper");
+
+    for invalid in &[
+        "",
+        "foo:3",
+        "sma:0",
+        "sma:1",
+        "sma:2.5",
+        "sma:x",
+        "sma:10,20",
+        "macd:26,12,9",
+        "bb:1",
+        "bb:20,-1",
+        "bb:20,2,0.5",
+        "rsi:0",
+        "rsi:10001",
+        "sma:1e18",
+        "sma:1e20",
+        "stoch:14,2e18",
+        "bb:1e18,2",
+    ] {
+        assert!(invalid.parse::<IndicatorSpec>().is_err(), "{}", invalid);
+    }
+
+    let specs = [
+        "sma:50",
+        "rsi",
+        "sma:50",
+        "bb:20,2",
+        "bb:20,2,1",
+        "drawdown",
+    ]
+    .iter()
+    .map(|s| s.parse().unwrap())
+    .collect();
+    let indicators = IndicatorSet::new(specs, 0.0);
+    assert_eq!(indicators.specs().len(), 5);
+    let columns = indicators.columns();
+    assert_eq!(columns.len(), 15);
+
+    let bars = wavy_bars(60);
+    let quote = Quote {
+        cycle: 1,
+        symbol: "AAPL".to_string(),
+        from: bars[0].timestamp,
+        bars: bars.clone(),
+    };
+    let computed = Processor::new(indicators).process(&quote).unwrap();
+    assert_eq!(
+        computed.values.keys().collect::<Vec<_>>(),
+        columns.iter().map(|c| &c.name).collect::<Vec<_>>()
+    );
+    let number = |value: Option<f64>| value.map(IndicatorValue::Number);
+    let sma = stream(StreamingSMA::new(50), &bars);
+    assert_eq!(computed.values["sma_50"], number(sma.last().copied()));
+    let sample = BollingerBands::new(20, 2.0, Deviation::Sample);
+    let bands = stream(StreamingBollingerBands::new(sample), &bars);
+    assert_eq!(
+        computed.values["bb_20_2_1_upper"],
+        number(bands.last().map(|b| b.upper))
+    );
+    assert!(computed.values["bb_20_2_1_upper"] > computed.values["bollinger_upper"]);
+
+    let drawdown = stream(StreamingMaxDrawdown::default(), &bars);
+    let drawdown = drawdown.last().unwrap();
+    let time = |i: usize| Some(IndicatorValue::Time(bars[i].timestamp));
+    assert_eq!(
+        computed.values["max_drawdown"],
+        number(Some(drawdown.depth))
+    );
+    assert_eq!(computed.values["drawdown_peak"], time(drawdown.peak));
+    assert_eq!(computed.values["drawdown_trough"], time(drawdown.trough));
+
+    let line = writer::format_line(&computed, &columns);
+    assert_eq!(line.split(',').count(), 6 + columns.len());
+    assert!(line.ends_with(&format!(
+        ",{},{}",
+        bars[drawdown.peak].timestamp.to_rfc3339(),
+        bars[drawdown.trough].timestamp.to_rfc3339()
+    )));
+    assert!(writer::header(&columns).ends_with(",sma_50,rsi_14,bollinger_upper,bollinger_middle,bollinger_lower,bollinger_percent_b,bollinger_bandwidth,\
+bb_20_2_1_upper,bb_20_2_1_middle,bb_20_2_1_lower,bb_20_2_1_percent_b,bb_20_2_1_bandwidth,\
+max_drawdown,drawdown_peak,drawdown_trough"));
+}
+
+fn sample_snapshot() -> snapshot::Snapshot {
+    snapshot::Snapshot {
+        saved_at: Utc.ymd(2022, 4, 2).and_hms(12, 0, 0),
+        indicators: vec![Indicators {
+            symbol: "AAPL".to_string(),
+            from: Utc.ymd(2022, 1, 1).and_hms(0, 0, 0),
+            timestamp: Utc.ymd(2022, 4, 1).and_hms(20, 0, 0),
+            last_price: 174.31,
+            pct_change: 0.02,
+            period_min: 150.62,
+            period_max: 182.01,
+            values: [
+                ("last_sma", Some(165.05)),
+                ("rsi_14", Some(61.2)),
+                ("macd_histogram", Some(0.25)),
+                ("bollinger_upper", None),
+                ("max_drawdown", Some(0.12)),
+                ("vwap", Some(172.5)),
+            ]
+            .iter()
+            .map(|(column, value)| (column.to_string(), value.map(IndicatorValue::Number)))
+            .chain(std::iter::once((
+                "drawdown_peak".to_string(),
+                Some(IndicatorValue::Time(Utc.ymd(2022, 1, 4).and_hms(20, 0, 0))),
+            )))
+            .collect(),
+        }],
+    }
+}
+
+#[test]
+fn test_snapshot_roundtrip() {
+    let bytes = snapshot::encode(&sample_snapshot()).unwrap();
+    let decoded = snapshot::decode(&bytes).unwrap();
+    assert_eq!(decoded.saved_at, sample_snapshot().saved_at);
+    assert_eq!(decoded.indicators.len(), 1);
+    assert_eq!(decoded.indicators[0].symbol, "AAPL");
+    let values = &decoded.indicators[0].values;
+    assert_eq!(values["last_sma"], Some(IndicatorValue::Number(165.05)));
+    assert_eq!(values["bollinger_upper"], None);
+    assert_eq!(
+        values["drawdown_peak"],
+        Some(IndicatorValue::Time(Utc.ymd(2022, 1, 4).and_hms(20, 0, 0)))
+    );
+    let json = serde_json::to_value(&decoded.indicators[0]).unwrap();
+    assert_eq!(json["drawdown_peak"], "2022-01-04T20:00:00Z");
+    assert_eq!(
+        decoded.indicators[0].values.keys().collect::<Vec<_>>(),
+        sample_snapshot().indicators[0]
+            .values
+            .keys()
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_snapshot_reads_version_2() {
+    // version 2 had a field for each default indicator instead of the map of columns
+    let payload = br#"{"saved_at":"2022-04-02T12:00:00Z","indicators":[{"symbol":"AAPL",
+        "from":"2022-01-01T00:00:00Z","timestamp":"2022-04-01T20:00:00Z","last_price":174.31,
+        "pct_change":0.02,"period_min":150.62,"period_max":182.01,"last_sma":165.05,
+        "last_ema":null,"max_drawdown":0.12,"drawdown_peak":"2022-01-04T20:00:00Z"}]}"#;
+    let mut bytes = format!(
+        "stock-tracker-snapshot 2 {:08x}\n",
+        crc32fast::hash(payload)
+    )
+    .into_bytes();
+    bytes.extend_from_slice(payload);
+
+    let decoded = snapshot::decode(&bytes).unwrap();
+    let values = &decoded.indicators[0].values;
+    assert_eq!(values["last_sma"], Some(IndicatorValue::Number(165.05)));
+    assert_eq!(values["last_ema"], None);
+    assert_eq!(
+        values["drawdown_peak"],
+        Some(IndicatorValue::Time(Utc.ymd(2022, 1, 4).and_hms(20, 0, 0)))
+    );
+
+    bytes[23] = b'1';
+    assert!(snapshot::decode(&bytes).is_err());
+}
+
+#[test]
+fn test_snapshot_rejects_corruption() {
+    let mut bytes = snapshot::encode(&sample_snapshot()).unwrap();
+    let last = bytes.len() - 2;
+    bytes[last] = b'9';
+    assert!(snapshot::decode(&bytes).is_err());
+
+    let bytes = snapshot::encode(&sample_snapshot()).unwrap();
+    let bytes = String::from_utf8(bytes)
+        .unwrap()
+        .replacen(&format!(" {} ", snapshot::SNAPSHOT_VERSION), " 999 ", 1)
+        .into_bytes();
+    assert!(snapshot::decode(&bytes).is_err());
+
+    assert!(snapshot::decode(b"").is_err());
+    assert!(snapshot::decode(b"not a snapshot\n{}").is_err());
+}
+
+#[test]
+fn test_parse_count() {
+    assert_eq!(http_server::tail::parse_count("n", "0").unwrap(), 0);
+    assert_eq!(http_server::tail::parse_count("n", "5").unwrap(), 5);
+    assert_eq!(
+        http_server::tail::parse_count("n", "1000").unwrap(),
+        http_server::tail::MAX_PAGE_SIZE
+    );
+    assert_eq!(http_server::tail::parse_count("n", "-0").unwrap(), 0);
+    assert_eq!(
+        http_server::tail::parse_count("n", "-1").unwrap_err().code,
+        "invalid_parameter"
+    );
+    assert_eq!(
+        http_server::tail::parse_count("n", "abc").unwrap_err().code,
+        "invalid_parameter"
+    );
+    assert_eq!(
+        http_server::tail::parse_count("n", "").unwrap_err().code,
+        "invalid_parameter"
+    );
+    assert_eq!(
+        http_server::tail::parse_count("n", "1001")
+            .unwrap_err()
+            .code,
+        "page_size_exceeded"
+    );
+    assert_eq!(
+        http_server::tail::parse_count("n", "9999999999999999999999999")
+            .unwrap_err()
+            .code,
+        "page_size_exceeded"
+    );
+}
+
+async fn send(
+    app: &tide::Server<AppState>,
+    method: Method,
+    path: &str,
+    body: Option<&str>,
+) -> Response {
+    let url = Url::parse("http://localhost").unwrap().join(path).unwrap();
+    let mut req = Request::new(method, url);
+    if let Some(body) = body {
+        req.set_body(body);
+    }
+    app.respond(req).await.unwrap()
+}
+
+async fn get(app: &tide::Server<AppState>, path: &str) -> Response {
+    send(app, Method::Get, path, None).await
+}
+
+async fn test_app() -> (AppState, tide::Server<AppState>) {
+    let data_holder = DataHolder::new().start().await.unwrap();
+    let symbols = vec!["AAPL".to_string(), "UBER".to_string()];
+    // long enough for no fetch cycle to run during a test
+    let interval = std::time::Duration::from_secs(3600);
+    let scheduler = Scheduler::new(symbols, Utc::now(), interval)
+        .start()
+        .await
+        .unwrap();
+    let metrics = Metrics::new().start().await.unwrap();
+    let state = AppState::new(data_holder, scheduler, metrics);
+    (state.clone(), http_server::build_app(state))
+}
+
+#[async_std::test]
+async fn test_json_errors() {
+    use http_server::error::json_errors;
+
+    for (status, code) in &[
+        (400, "invalid_body"),
+        (422, "invalid_body"),
+        (409, "error"),
+        (500, "internal_error"),
+        (503, "internal_error"),
+    ] {
+        let err = tide::Error::from_str(*status, "failed");
+        let mut res: Response = json_errors(err.into()).await.unwrap().into();
+        assert_eq!(res.status(), *status);
+        let body: serde_json::Value = res.body_json().await.unwrap();
+        assert_eq!(body["error"]["code"], *code);
+        assert_eq!(body["error"]["message"], "failed");
+    }
+}
+
+#[async_std::test]
+async fn test_tail_endpoint() {
+    let (_, app) = test_app().await;
+    let mut res = get(&app, "/tail/5").await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.content_type(), Some(tide::http::mime::JSON));
+    assert_eq!(res.body_string().await.unwrap(), "[]");
+
+    for path in &["/tail/-1", "/tail/9999999999999"] {
+        let mut res = get(&app, path).await;
+        assert_eq!(res.status(), 400);
+        assert_eq!(res.content_type(), Some(tide::http::mime::JSON));
+        let body: serde_json::Value = res.body_json().await.unwrap();
+        assert!(body["error"]["code"].is_string());
+    }
+
+    let res = get(&app, "/nope").await;
+    assert_eq!(res.status(), 404);
+    assert_eq!(res.content_type(), Some(tide::http::mime::JSON));
+}
+
+#[async_std::test]
+async fn test_symbol_endpoints() {
+    let (state, app) = test_app().await;
+    let data_holder = state.data_holder;
+    let mut indicators = sample_snapshot().indicators.remove(0);
+    data_holder
+        .send(FetchStatus {
+            cycle: 0,
+            symbol: "AAPL".to_string(),
+            fetched_at: Utc::now(),
+            duration: std::time::Duration::from_millis(20),
+            error: None,
+            error_kind: None,
+        })
+        .unwrap();
+    data_holder
+        .send(FetchStatus {
+            cycle: 0,
+            symbol: "UBER".to_string(),
+            fetched_at: Utc::now(),
+            duration: std::time::Duration::from_millis(20),
+            error: Some("connection failed".to_string()),
+            error_kind: Some(FetchErrorKind::Connection),
+        })
+        .unwrap();
+    data_holder.send(indicators.clone()).unwrap();
+    indicators.timestamp = Utc.ymd(2022, 4, 2).and_hms(20, 0, 0);
+    indicators.last_price = 178.44;
+    data_holder.send(indicators).unwrap();
+
+    let mut res = get(&app, "/symbols").await;
+    let body: serde_json::Value = res.body_json().await.unwrap();
+    assert_eq!(body[0]["symbol"], "AAPL");
+    assert_eq!(body[0]["status"], "ok");
+    assert_eq!(body[1]["symbol"], "UBER");
+    assert_eq!(body[1]["status"], "failing");
+    assert_eq!(body[1]["error_count"], 1);
+
+    let mut res = get(&app, "/symbols/aapl").await;
+    let body: serde_json::Value = res.body_json().await.unwrap();
+    assert_eq!(body["last_price"], 178.44);
+
+    let mut res = get(&app, "/symbols/AAPL/history?to=2022-04-01T23:00:00Z").await;
+    let body: Vec<Indicators> = res.body_json().await.unwrap();
+    assert_eq!(body.len(), 1);
+    assert_eq!(body[0].last_price, 174.31);
+
+    let mut res = get(&app, "/symbols/AAPL/history?limit=1").await;
+    let body: Vec<Indicators> = res.body_json().await.unwrap();
+    assert_eq!(body.len(), 1);
+    assert_eq!(body[0].last_price, 178.44);
+
+    let res = get(&app, "/symbols/AAPL/history?from=yesterday").await;
+    assert_eq!(res.status(), 400);
+    let res = get(&app, "/symbols/MSFT").await;
+    assert_eq!(res.status(), 404);
+    let res = get(&app, "/symbols/UBER/bars").await;
+    assert_eq!(res.status(), 404);
+}
+
+#[async_std::test]
+async fn test_events_endpoint_resumes() {
+    use async_std::io::prelude::BufReadExt;
+
+    let (state, app) = test_app().await;
+    let data_holder = state.data_holder;
+    let mut indicators = sample_snapshot().indicators.remove(0);
+    data_holder.send(indicators.clone()).unwrap();
+    indicators.symbol = "MSFT".to_string();
+    data_holder.send(indicators.clone()).unwrap();
+    indicators.symbol = "AAPL".to_string();
+    data_holder.send(indicators).unwrap();
+
+    let url = Url::parse("http://localhost/events?symbols=msft").unwrap();
+    let mut req = Request::new(Method::Get, url);
+    req.insert_header("Last-Event-ID", "1");
+    let mut res: Response = app.respond(req).await.unwrap();
+    assert_eq!(res.content_type(), Some(tide::http::mime::SSE));
+
+    let mut body = res.take_body();
+    let mut lines = vec![];
+    while !lines.iter().any(|l: &String| l.starts_with("data: ")) {
+        let mut line = String::new();
+        async_std::future::timeout(std::time::Duration::from_secs(1), body.read_line(&mut line))
+            .await
+            .unwrap()
+            .unwrap();
+        lines.push(line);
+    }
+
+    assert!(lines.contains(&"id: 2\n".to_string()));
+    assert!(lines.contains(&"event: indicators\n".to_string()));
+    assert!(lines.last().unwrap().contains("\"symbol\":\"MSFT\""));
+}
+
+#[async_std::test]
+async fn test_events_endpoint_resets_after_dropped_events() {
+    use async_std::io::prelude::BufReadExt;
+
+    let (state, app) = test_app().await;
+    let data_holder = state.data_holder;
+    let indicators = sample_snapshot().indicators.remove(0);
+    for _ in 0..MAX_REPLAY + 3 {
+        data_holder.send(indicators.clone()).unwrap();
+    }
+
+    let url = Url::parse("http://localhost/events").unwrap();
+    let mut req = Request::new(Method::Get, url);
+    req.insert_header("Last-Event-ID", "1");
+    let mut res: Response = app.respond(req).await.unwrap();
+
+    let mut body = res.take_body();
+    let mut lines = vec![];
+    while !lines.iter().any(|l: &String| l.starts_with("data: ")) {
+        let mut line = String::new();
+        async_std::future::timeout(std::time::Duration::from_secs(1), body.read_line(&mut line))
+            .await
+            .unwrap()
+            .unwrap();
+        lines.push(line);
+    }
+
+    // the reset comes ahead of the replayed indicators
+    assert_eq!(lines[lines.len() - 2], "event: reset\n");
+    assert_eq!(lines.last().unwrap(), "data: {\"dropped\":2}\n");
+}
+
+#[test]
+fn test_ws_protocol() {
+    use http_server::ws::*;
+
+    let msg: ClientMessage =
+        serde_json::from_str(r#"{"type":"subscribe","symbols":["aapl","MSFT"]}"#).unwrap();
+    assert_eq!(
+        msg,
+        ClientMessage::Subscribe {
+            symbols: vec!["aapl".to_string(), "MSFT".to_string()]
+        }
+    );
+    let msg: ClientMessage = serde_json::from_str(r#"{"type":"snapshot"}"#).unwrap();
+    assert_eq!(msg, ClientMessage::Snapshot { symbols: vec![] });
+    assert!(serde_json::from_str::<ClientMessage>(r#"{"type":"buy"}"#).is_err());
+
+    let pong = serde_json::to_value(&ServerMessage::Pong {
+        id: Some(serde_json::json!(7)),
+    })
+    .unwrap();
+    assert_eq!(pong, serde_json::json!({"type": "pong", "id": 7}));
+
+    let mut subscriptions = Subscriptions::default();
+    assert!(subscriptions.is_empty());
+    subscriptions.subscribe(&["msft".to_string(), "AAPL".to_string()]);
+    assert_eq!(subscriptions.list(), vec!["AAPL", "MSFT"]);
+    assert!(subscriptions.matches("MSFT"));
+    assert!(!subscriptions.matches("UBER"));
+    subscriptions.unsubscribe(&["MSFT".to_string()]);
+    assert!(!subscriptions.matches("MSFT"));
+    subscriptions.subscribe(&[WILDCARD.to_string()]);
+    assert!(subscriptions.matches("UBER"));
+    assert_eq!(subscriptions.list(), vec![WILDCARD]);
+    subscriptions.unsubscribe(&[WILDCARD.to_string()]);
+    assert!(subscriptions.is_empty());
+}
+
+#[async_std::test]
+async fn test_symbol_control_endpoints() {
+    let (_, app) = test_app().await;
+
+    let mut res = send(
+        &app,
+        Method::Post,
+        "/symbols",
+        Some(r#"{"symbols":["msft","brk.b","^gspc"]}"#),
+    )
+    .await;
+    assert_eq!(res.status(), 200);
+    let body: Vec<SymbolStatus> = res.body_json().await.unwrap();
+    let symbols: Vec<&str> = body.iter().map(|s| s.symbol.as_str()).collect();
+    assert_eq!(symbols, vec!["AAPL", "BRK.B", "MSFT", "UBER", "^GSPC"]);
+    assert_eq!(body[2].status, SymbolState::Pending);
+
+    // the client percent-encodes the symbol of the path
+    let path = client::symbol_path("^GSPC");
+    assert_eq!(path, "/symbols/%5EGSPC");
+    let res = send(&app, Method::Delete, &path, None).await;
+    assert_eq!(res.status(), 204);
+
+    let res = send(
+        &app,
+        Method::Post,
+        "/symbols",
+        Some(r#"{"symbols":["no pe"]}"#),
+    )
+    .await;
+    assert_eq!(res.status(), 400);
+    let res = send(&app, Method::Post, "/symbols", Some(r#"{"symbols":[]}"#)).await;
+    assert_eq!(res.status(), 400);
+
+    let res = send(&app, Method::Delete, "/symbols/uber", None).await;
+    assert_eq!(res.status(), 204);
+    let res = send(&app, Method::Delete, "/symbols/UBER", None).await;
+    assert_eq!(res.status(), 404);
+
+    let mut res = get(&app, "/symbols").await;
+    let body: Vec<SymbolStatus> = res.body_json().await.unwrap();
+    assert_eq!(body.len(), 3);
+    assert!(body.iter().all(|s| s.symbol != "UBER"));
+}
+
+#[async_std::test]
+async fn test_refresh_endpoints() {
+    let (state, app) = test_app().await;
+    let processed = |cycle, symbol: &str, indicators| SymbolProcessed {
+        cycle,
+        symbol: symbol.to_string(),
+        indicators,
+    };
+    let indicators = sample_snapshot().indicators.remove(0);
+
+    let mut res = send(
+        &app,
+        Method::Post,
+        "/refresh?wait=false",
+        Some(r#"{"symbols":["aapl","AAPL"]}"#),
+    )
+    .await;
+    assert_eq!(res.status(), 202);
+    assert_eq!(res.header("Location").unwrap(), "/refresh/1");
+    let job: RefreshJob = res.body_json().await.unwrap();
+    assert_eq!(job.state, JobState::Running);
+    assert_eq!(job.symbols, vec!["AAPL"]);
+
+    // coalesced behind the running cycle instead of overlapping it
+    let mut res = send(&app, Method::Post, "/refresh?wait=false", None).await;
+    let job: RefreshJob = res.body_json().await.unwrap();
+    assert_eq!((job.id, job.state), (2, JobState::Queued));
+    assert_eq!(job.symbols, vec!["AAPL", "UBER"]);
+
+    state
+        .scheduler
+        .send(processed(1, "AAPL", Some(indicators.clone())))
+        .unwrap();
+    let mut res = get(&app, "/refresh/1").await;
+    let job: RefreshJob = res.body_json().await.unwrap();
+    assert_eq!(job.state, JobState::Done);
+    assert_eq!(job.indicators.len(), 1);
+    let status = state.scheduler.call(GetSchedulerStatus).await.unwrap();
+    assert_eq!(status.last_cycle.unwrap().symbols, 1);
+    let mut res = get(&app, "/refresh/2").await;
+    let job: RefreshJob = res.body_json().await.unwrap();
+    assert_eq!(job.state, JobState::Running);
+
+    state
+        .scheduler
+        .send(FetchStatus {
+            cycle: 2,
+            symbol: "UBER".to_string(),
+            fetched_at: Utc::now(),
+            duration: std::time::Duration::from_millis(20),
+            error: Some("connection failed".to_string()),
+            error_kind: Some(FetchErrorKind::Connection),
+        })
+        .unwrap();
+    state.scheduler.send(processed(2, "AAPL", None)).unwrap();
+    let mut res = get(&app, "/refresh/2").await;
+    let job: RefreshJob = res.body_json().await.unwrap();
+    assert_eq!(job.state, JobState::Done);
+    assert_eq!(job.errors["UBER"], "connection failed");
+
+    let scheduler = state.scheduler.clone();
+    let complete = async move {
+        async_std::task::sleep(std::time::Duration::from_millis(50)).await;
+        scheduler
+            .send(processed(3, "AAPL", Some(indicators)))
+            .unwrap();
+    };
+    let (mut res, _) = futures::join!(
+        send(
+            &app,
+            Method::Post,
+            "/refresh",
+            Some(r#"{"symbols":["AAPL"]}"#)
+        ),
+        complete
+    );
+    assert_eq!(res.status(), 200);
+    let job: RefreshJob = res.body_json().await.unwrap();
+    assert_eq!((job.id, job.state), (3, JobState::Done));
+
+    let res = send(
+        &app,
+        Method::Post,
+        "/refresh",
+        Some(r#"{"symbols":["MSFT"]}"#),
+    )
+    .await;
+    assert_eq!(res.status(), 404);
+    let res = get(&app, "/refresh/99").await;
+    assert_eq!(res.status(), 404);
+}
+
+#[async_std::test]
+async fn test_refresh_keeps_unfinished_jobs() {
+    let (state, app) = test_app().await;
+    for _ in 0..102 {
+        let _ = state
+            .scheduler
+            .call(Refresh(vec!["AAPL".to_string()]))
+            .await
+            .unwrap();
+    }
+    state
+        .scheduler
+        .send(SymbolProcessed {
+            cycle: 1,
+            symbol: "AAPL".to_string(),
+            indicators: None,
+        })
+        .unwrap();
+
+    // the finished job makes room, the coalesced ones still run and can be polled
+    assert_eq!(get(&app, "/refresh/1").await.status(), 404);
+    for id in [2, 102] {
+        let mut res = get(&app, &format!("/refresh/{}", id)).await;
+        assert_eq!(res.status(), 200);
+        let job: RefreshJob = res.body_json().await.unwrap();
+        assert_eq!(job.state, JobState::Running);
+    }
+}
+
+#[async_std::test]
+async fn test_health_endpoints() {
+    let (state, app) = test_app().await;
+    let mut res = get(&app, "/healthz").await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.body_string().await.unwrap(), r#"{"status":"ok"}"#);
+
+    let mut res = get(&app, "/readyz").await;
+    assert_eq!(res.status(), 503);
+    let body: serde_json::Value = res.body_json().await.unwrap();
+    assert_eq!(body["error"]["code"], "not_ready");
+
+    let fetch_status = |cycle, symbol: &str| FetchStatus {
+        cycle,
+        symbol: symbol.to_string(),
+        fetched_at: Utc::now(),
+        duration: std::time::Duration::from_millis(20),
+        error: Some("connection failed".to_string()),
+        error_kind: Some(FetchErrorKind::Connection),
+    };
+
+    // a cycle where every fetch failed doesn't make the tracker ready
+    send(&app, Method::Post, "/refresh?wait=false", None).await;
+    state.scheduler.send(fetch_status(1, "AAPL")).unwrap();
+    state.scheduler.send(fetch_status(1, "UBER")).unwrap();
+    assert_eq!(get(&app, "/readyz").await.status(), 503);
+
+    send(&app, Method::Post, "/refresh?wait=false", None).await;
+    state.scheduler.send(fetch_status(2, "AAPL")).unwrap();
+    state
+        .scheduler
+        .send(SymbolProcessed {
+            cycle: 2,
+            symbol: "UBER".to_string(),
+            indicators: None,
+        })
+        .unwrap();
+    let mut res = get(&app, "/readyz").await;
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.body_json().await.unwrap();
+    assert_eq!(body["last_successful_cycle"]["id"], 2);
+    assert_eq!(body["last_successful_cycle"]["failed"], 1);
+
+    let mut res = get(&app, "/status").await;
+    assert_eq!(res.status(), 200);
+    let status: serde_json::Value = res.body_json().await.unwrap();
+    assert_eq!(status["actors"]["data_holder"], "running");
+    assert_eq!(status["actors"]["scheduler"], "running");
+    assert_eq!(
+        status["tracked_symbols"],
+        serde_json::json!(["AAPL", "UBER"])
+    );
+    assert_eq!(status["scheduler"]["cycles_completed"], 2);
+    assert_eq!(status["scheduler"]["last_cycle"]["id"], 2);
+    assert_eq!(status["errors"]["fetch_errors"], 3);
+    assert!(status["uptime_secs"].as_i64().unwrap() >= 0);
+}
+
+#[async_std::test]
+async fn test_metrics_endpoint() {
+    let (state, app) = test_app().await;
+    for (symbol, error_kind) in [("AAPL", None), ("UBER", Some(FetchErrorKind::Connection))] {
+        state
+            .metrics
+            .send(FetchStatus {
+                cycle: 1,
+                symbol: symbol.to_string(),
+                fetched_at: Utc::now(),
+                duration: std::time::Duration::from_millis(300),
+                error: error_kind.map(|_| "connection failed".to_string()),
+                error_kind,
+            })
+            .unwrap();
+    }
+    get(&app, "/symbols/aapl").await;
+    get(&app, "/nowhere/to/be/found").await;
+    get(&app, "/tail").await;
+    get(&app, "/openapi.json").await;
+    get(&app, "/history").await;
+
+    let mut res = get(&app, "/metrics").await;
+    assert_eq!(res.status(), 200);
+    assert!(res
+        .header("Content-Type")
+        .unwrap()
+        .as_str()
+        .starts_with("text/plain; version=0.0.4"));
+    let body = res.body_string().await.unwrap();
+    let lines: Vec<&str> = body.lines().collect();
+    for expected in &[
+        r#"stock_tracker_fetch_duration_seconds_bucket{symbol="AAPL",le="0.25"} 0"#,
+        r#"stock_tracker_fetch_duration_seconds_bucket{symbol="AAPL",le="0.5"} 1"#,
+        r#"stock_tracker_fetch_duration_seconds_bucket{symbol="AAPL",le="+Inf"} 1"#,
+        r#"stock_tracker_fetch_duration_seconds_count{symbol="UBER"} 1"#,
+        r#"stock_tracker_fetch_errors_total{kind="connection"} 1"#,
+        "stock_tracker_quotes_processed_total 0",
+        r#"stock_tracker_http_requests_total{method="GET",route="/symbols/:symbol",status="404"} 1"#,
+        r#"stock_tracker_http_requests_total{method="GET",route="other",status="404"} 2"#,
+        r#"stock_tracker_http_requests_total{method="GET",route="/openapi.json",status="200"} 1"#,
+        r#"stock_tracker_http_requests_total{method="GET",route="/history",status="200"} 1"#,
+        "stock_tracker_data_holder_indicators 0",
+        "# TYPE stock_tracker_actor_ping_seconds gauge",
+    ] {
+        assert!(lines.contains(expected), "missing {}", expected);
+    }
+    assert!(lines
+        .iter()
+        .any(|l| l.starts_with(r#"stock_tracker_actor_ping_seconds{actor="scheduler"} 0"#)));
+
+    assert_eq!(
+        http_server::metrics::route_label("/symbols/AAPL/history"),
+        "/symbols/:symbol/history"
+    );
+    assert_eq!(http_server::metrics::route_label("/refresh"), "/refresh");
+    assert_eq!(
+        http_server::metrics::route_label("/refresh/3"),
+        "/refresh/:id"
+    );
+    assert_eq!(http_server::metrics::route_label("/tail"), "other");
+
+    // every registered route gets a label of its own
+    for route in http_server::ROUTES {
+        let path: Vec<&str> = route
+            .path
+            .split('/')
+            .map(|s| if s.starts_with(':') { "1" } else { s })
+            .collect();
+        assert_eq!(
+            http_server::metrics::route_label(&path.join("/")),
+            route.path
+        );
+    }
+}
+
+#[async_std::test]
+async fn test_http_server_listeners() {
+    let (state, _) = test_app().await;
+    let server = http_server::HttpServer::new(state).start().await.unwrap();
+
+    let any_port: std::net::SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let urls = server
+        .call(StartHttpServer(vec![any_port, any_port]))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(urls.len(), 2);
+    for url in &urls {
+        let res = client::request(url, None, Method::Get, "/healthz", None)
+            .await
+            .unwrap();
+        assert_eq!((res.status, res.body.as_str()), (200, r#"{"status":"ok"}"#));
+    }
+
+    // an address that is already taken is reported instead of panicking
+    let port = surf::Url::parse(&urls[0]).unwrap().port().unwrap();
+    let taken = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+    let err = server
+        .call(StartHttpServer(vec![any_port, taken]))
+        .await
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+    assert!(err.to_string().contains(&taken.to_string()));
+}
+
+#[async_std::test]
+async fn test_auth_and_cors() {
+    let (state, _) = test_app().await;
+    let tokens = http_server::auth::Tokens::new(vec!["reader".into()], vec!["admin".into()]);
+    let app = http_server::build_app(
+        state
+            .with_tokens(tokens)
+            .with_cors(vec!["https://dashboard.example".into()]),
+    );
+    let respond = |method, path: &str, token: Option<&str>| {
+        let url = Url::parse("http://localhost").unwrap().join(path).unwrap();
+        let mut req = Request::new(method, url);
+        req.insert_header("Origin", "https://dashboard.example");
+        if let Some(token) = token {
+            req.insert_header("Authorization", format!("Bearer {}", token));
+        }
+        app.respond(req)
+    };
+
+    let res: Response = respond(Method::Get, "/healthz", None).await.unwrap();
+    assert_eq!(res.status(), 200);
+
+    let mut res: Response = respond(Method::Get, "/symbols", None).await.unwrap();
+    assert_eq!(res.status(), 401);
+    assert_eq!(res.header("WWW-Authenticate").unwrap(), "Bearer");
+    assert_eq!(
+        res.header("Access-Control-Allow-Origin").unwrap(),
+        "https://dashboard.example"
+    );
+    let body: serde_json::Value = res.body_json().await.unwrap();
+    assert_eq!(body["error"]["code"], "unauthorized");
+
+    let res: Response = respond(Method::Get, "/symbols", Some("wrong"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+    let res: Response = respond(Method::Get, "/symbols", Some("reader"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    // only the streaming routes take the token from the query
+    let res: Response = respond(Method::Get, "/symbols?access_token=reader", None)
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+    let res: Response = respond(Method::Get, "/events?access_token=reader", None)
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    let res: Response = respond(Method::Delete, "/symbols/UBER", Some("reader"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+    let res: Response = respond(Method::Delete, "/symbols/UBER", Some("admin"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+
+    // preflight requests are answered without a token
+    let res: Response = respond(Method::Options, "/symbols", None).await.unwrap();
+    assert_eq!(res.status(), 200);
+    assert!(res
+        .header("Access-Control-Allow-Headers")
+        .unwrap()
+        .as_str()
+        .contains("Authorization"));
+
+    // any origin is allowed once `*` is among the listed ones
+    let (state, _) = test_app().await;
+    let app = http_server::build_app(
+        state.with_cors(vec!["https://dashboard.example".into(), "*".into()]),
+    );
+    let url = Url::parse("http://localhost/healthz").unwrap();
+    let mut req = Request::new(Method::Get, url);
+    req.insert_header("Origin", "https://other.example");
+    let res: Response = app.respond(req).await.unwrap();
+    assert_eq!(res.header("Access-Control-Allow-Origin").unwrap(), "*");
+}
+
+fn collect_refs<'a>(value: &'a serde_json::Value, refs: &mut Vec<&'a str>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            if let Some(serde_json::Value::String(r)) = map.get("$ref") {
+                refs.push(r);
+            }
+            map.values().for_each(|v| collect_refs(v, refs));
+        }
+        serde_json::Value::Array(values) => values.iter().for_each(|v| collect_refs(v, refs)),
+        _ => {}
+    }
+}
+
+#[async_std::test]
+async fn test_openapi_document() {
+    let (_, app) = test_app().await;
+    let mut res = get(&app, "/openapi.json").await;
+    assert_eq!(res.status(), 200);
+    let doc: serde_json::Value = res.body_json().await.unwrap();
+    assert_eq!(doc["openapi"], "3.0.3");
+
+    // every registered route is documented, and only those
+    let documented: Vec<(String, String)> = doc["paths"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .flat_map(|(path, item)| {
+            item.as_object()
+                .unwrap()
+                .iter()
+                .map(move |(method, operation)| {
+                    assert!(
+                        operation["summary"].is_string(),
+                        "{} {} isn't documented",
+                        method,
+                        path
+                    );
+                    (method.clone(), path.clone())
+                })
+        })
+        .collect();
+    let registered: Vec<(String, String)> = http_server::ROUTES
+        .iter()
+        .map(|r| {
+            let method = r.method.to_string().to_lowercase();
+            (method, http_server::openapi::openapi_path(r.path))
+        })
+        .collect();
+    assert!(registered.len() > 10);
+    assert_eq!(documented.len(), registered.len());
+    assert!(registered.iter().all(|r| documented.contains(r)));
+    assert_eq!(
+        http_server::openapi::openapi_path("/symbols/:symbol/history"),
+        "/symbols/{symbol}/history"
+    );
+
+    let mut refs = vec![];
+    collect_refs(&doc, &mut refs);
+    for r in refs {
+        let name = r.strip_prefix("#/components/schemas/").unwrap();
+        assert!(doc["components"]["schemas"].get(name).is_some(), "{}", r);
+    }
+    let indicators = &doc["components"]["schemas"]["Indicators"];
+    assert!(indicators["properties"].get("last_price").is_some());
+    let value = &indicators["additionalProperties"]["oneOf"];
+    assert_eq!(value[0]["type"], "number");
+    assert_eq!(value[1]["format"], "date-time");
+}
+
+#[async_std::test]
+async fn test_dashboard() {
+    let (state, _) = test_app().await;
+    let tokens = http_server::auth::Tokens::new(vec!["reader".into()], vec![]);
+    let app = http_server::build_app(state.with_tokens(tokens));
+
+    // the page loads without a token and asks for one once the API refuses
+    let mut res = get(&app, "/").await;
+    assert_eq!(res.status(), 200);
+    assert!(res
+        .content_type()
+        .unwrap()
+        .essence()
+        .starts_with("text/html"));
+    let page = res.body_string().await.unwrap();
+    assert!(page.contains("<table"));
+    assert!(page.contains("new EventSource("));
+    for external in &["src=\"http", "href=\"http", "@import", "url(http"] {
+        assert!(!page.contains(external), "page loads {}", external);
+    }
+}
+
+#[async_std::test]
+async fn test_content_negotiation() {
+    let (state, _) = test_app().await;
+    let columns = [
+        "last_sma",
+        "rsi_14",
+        "macd_histogram",
+        "bollinger_upper",
+        "stochastic_k",
+    ];
+    let app = http_server::build_app(
+        state
+            .clone()
+            .with_config(http_server::health::ConfigSummary {
+                columns: columns
+                    .iter()
+                    .map(|c| Column {
+                        name: c.to_string(),
+                        unit: Unit::Score,
+                    })
+                    .collect(),
+                ..Default::default()
+            }),
+    );
+    let mut indicators = sample_snapshot().indicators.remove(0);
+    for day in 1..=20 {
+        indicators.timestamp = Utc.ymd(2022, 3, day).and_hms(20, 0, 0);
+        state.data_holder.send(indicators.clone()).unwrap();
+    }
+    let respond = |path: &str, headers: &[(&str, &str)]| {
+        let url = Url::parse("http://localhost").unwrap().join(path).unwrap();
+        let mut req = Request::new(Method::Get, url);
+        for (name, value) in headers {
+            req.insert_header(*name, *value);
+        }
+        app.respond(req)
+    };
+
+    // the dashboard formats the columns by their unit
+    let mut res: Response = respond("/status", &[]).await.unwrap();
+    let status: serde_json::Value = res.body_json().await.unwrap();
+    assert_eq!(
+        status["config"]["columns"][0],
+        serde_json::json!({ "name": "last_sma", "unit": "score" })
+    );
+
+    let mut res: Response = respond("/tail/2", &[("Accept", "text/csv")]).await.unwrap();
+    assert_eq!(
+        res.header("Content-Type").unwrap(),
+        "text/csv; charset=utf-8"
+    );
+    assert_eq!(
+        res.body_string().await.unwrap(),
+        "symbol,timestamp,from,last_price,pct_change,period_min,period_max,\
+         last_sma,rsi_14,macd_histogram,bollinger_upper,stochastic_k\n\
+         AAPL,2022-03-20T20:00:00+00:00,2022-01-01T00:00:00+00:00,174.31,0.02,150.62,182.01,165.05,61.2,0.25,,\n\
+         AAPL,2022-03-19T20:00:00+00:00,2022-01-01T00:00:00+00:00,174.31,0.02,150.62,182.01,165.05,61.2,0.25,,\n"
+    );
+
+    // the query parameter wins over the Accept header
+    let mut res: Response = respond(
+        "/symbols/AAPL/history?format=jsonl",
+        &[("Accept", "text/csv")],
+    )
+    .await
+    .unwrap();
+    assert_eq!(res.header("Content-Type").unwrap(), "application/x-ndjson");
+    let body = res.body_string().await.unwrap();
+    let lines: Vec<Indicators> = body
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 20);
+
+    let accept = [(
+        "Accept",
+        "text/html;q=0.9, application/json;q=0.5, text/csv;q=0",
+    )];
+    let res: Response = respond("/tail/1", &accept).await.unwrap();
+    assert_eq!(res.header("Content-Type").unwrap(), "application/json");
+    let res: Response = respond("/tail/1", &[("Accept", "image/png")])
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 406);
+    let res: Response = respond("/tail/1?format=xml", &[]).await.unwrap();
+    assert_eq!(res.status(), 400);
+
+    let plain = get(&app, "/symbols/AAPL/history")
+        .await
+        .body_bytes()
+        .await
+        .unwrap();
+    assert!(plain.len() >= http_server::format::MIN_COMPRESS_SIZE);
+    let encoding = [("Accept-Encoding", "deflate;q=0.5, gzip")];
+    let mut res: Response = respond("/symbols/AAPL/history", &encoding).await.unwrap();
+    assert_eq!(res.header("Content-Encoding").unwrap(), "gzip");
+    let compressed = res.body_bytes().await.unwrap();
+    assert!(compressed.len() < plain.len());
+    let mut decompressed = vec![];
+    std::io::Read::read_to_end(
+        &mut flate2::read::GzDecoder::new(&compressed[..]),
+        &mut decompressed,
+    )
+    .unwrap();
+    assert_eq!(decompressed, plain);
+
+    // small bodies aren't worth compressing
+    let res: Response = respond("/tail/1", &encoding).await.unwrap();
+    assert!(res.header("Content-Encoding").is_none());
+}
+
+#[async_std::test]
+async fn test_history_pagination() {
+    let (state, app) = test_app().await;
+    let mut indicators = sample_snapshot().indicators.remove(0);
+    for day in 1..=5 {
+        for symbol in &["AAPL", "UBER"] {
+            indicators.symbol = symbol.to_string();
+            indicators.timestamp = Utc.ymd(2022, 3, day).and_hms(20, 0, 0);
+            state.data_holder.send(indicators.clone()).unwrap();
+        }
+    }
+    let days =
+        |body: &[Indicators]| -> Vec<u32> { body.iter().map(|i| i.timestamp.day()).collect() };
+
+    // walk all pages by following the links
+    let mut path = "/symbols/AAPL/history?limit=2&from=2022-03-02T00:00:00Z".to_string();
+    let mut pages = vec![];
+    loop {
+        let mut res = get(&app, &path).await;
+        assert_eq!(res.status(), 200);
+        let link = res.header("Link").map(|l| l.as_str().to_string());
+        pages.push(days(&res.body_json::<Vec<Indicators>>().await.unwrap()));
+        match link {
+            Some(link) => {
+                assert!(link.ends_with(">; rel=\"next\""));
+                path = link[1..link.find('>').unwrap()].to_string();
+                assert!(path.contains("from=2022-03-02"));
+            }
+            None => break,
+        }
+    }
+    assert_eq!(pages, vec![vec![5, 4], vec![3, 2]]);
+
+    let mut res = get(&app, "/symbols/AAPL/history?order=asc&limit=3").await;
+    let link = res.header("Link").unwrap().as_str().to_string();
+    assert_eq!(
+        days(&res.body_json::<Vec<Indicators>>().await.unwrap()),
+        vec![1, 2, 3]
+    );
+    let next = &link[1..link.find('>').unwrap()];
+
+    // a page that can't be sent in an acceptable format has no next page
+    let url = Url::parse("http://localhost")
+        .unwrap()
+        .join("/symbols/AAPL/history?order=asc&limit=3")
+        .unwrap();
+    let mut req = Request::new(Method::Get, url);
+    req.insert_header("Accept", "text/html");
+    let res: Response = app.respond(req).await.unwrap();
+    assert_eq!(res.status(), 406);
+    assert!(res.header("Link").is_none());
+
+    let mut res = get(&app, next).await;
+    assert!(res.header("Link").is_none());
+    assert_eq!(
+        days(&res.body_json::<Vec<Indicators>>().await.unwrap()),
+        vec![4, 5]
+    );
+
+    let mut res = get(
+        &app,
+        "/symbols/AAPL/history?offset=1&limit=2&to=2022-03-04T23:00:00Z",
+    )
+    .await;
+    assert_eq!(
+        days(&res.body_json::<Vec<Indicators>>().await.unwrap()),
+        vec![3, 2]
+    );
+
+    let mut res = get(&app, "/history?symbols=uber&limit=1000").await;
+    let body: Vec<Indicators> = res.body_json().await.unwrap();
+    assert_eq!(body.len(), 5);
+    assert!(body.iter().all(|i| i.symbol == "UBER"));
+    let mut res = get(&app, "/history?order=asc&limit=3").await;
+    let body: Vec<Indicators> = res.body_json().await.unwrap();
+    let symbols: Vec<&str> = body.iter().map(|i| i.symbol.as_str()).collect();
+    assert_eq!(symbols, vec!["AAPL", "UBER", "AAPL"]);
+
+    let mut res = get(&app, "/history?symbols=AAPL,MSFT").await;
+    assert_eq!(res.status(), 404);
+    let body: serde_json::Value = res.body_json().await.unwrap();
+    assert!(body["error"]["message"].as_str().unwrap().contains("MSFT"));
+    for path in &[
+        "/history?order=sideways",
+        "/history?cursor=-1",
+        "/history?symbols=A%20B",
+    ] {
+        assert_eq!(get(&app, path).await.status(), 400, "{}", path);
+    }
+}